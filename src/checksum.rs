@@ -0,0 +1,129 @@
+use rand::{Rng, rngs::StdRng};
+
+/// identifier with check digits that `card`, `iban`, `isbn` and `ean` generate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Identifier {
+    /// published test card number, of any brand if none is given
+    Card(Option<CardBrand>),
+    /// IBAN of the country with the given ISO 3166 code
+    Iban(&'static str),
+    Isbn13,
+    Ean13,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CardBrand {
+    Visa,
+    Mastercard,
+    Amex,
+    Discover,
+}
+
+impl CardBrand {
+    pub const NAMES: &[(&str, CardBrand)] = &[
+        ("visa", CardBrand::Visa),
+        ("mastercard", CardBrand::Mastercard),
+        ("amex", CardBrand::Amex),
+        ("discover", CardBrand::Discover),
+    ];
+
+    /// card numbers the documentation of payment providers such as Stripe and Braintree lists for
+    /// testing. they're never issued to anyone, unlike arbitrary numbers that pass the Luhn check
+    pub(crate) fn test_numbers(self) -> &'static [&'static str] {
+        match self {
+            CardBrand::Visa => &["4242424242424242", "4111111111111111", "4012888888881881", "4000056655665556"],
+            CardBrand::Mastercard => &["5555555555554444", "5105105105105100", "2223003122003222", "5200828282828210"],
+            CardBrand::Amex => &["378282246310005", "371449635398431", "378734493671000"],
+            CardBrand::Discover => &["6011111111111117", "6011000990139424"],
+        }
+    }
+}
+
+/// countries `iban(...)` knows, with the layout of their account numbers: `#` is a digit and `A`
+/// an uppercase letter
+pub const IBAN_COUNTRIES: &[(&str, &str)] = &[
+    ("DE", "##################"),
+    ("GB", "AAAA##############"),
+    ("FR", "#######################"),
+    ("ES", "####################"),
+    ("IT", "A######################"),
+    ("NL", "AAAA##########"),
+    ("CH", "#################"),
+    ("AT", "################"),
+];
+
+impl Identifier {
+    /// the identifier with correct check digits, or with wrong ones if `invalid`
+    pub(crate) fn generate(self, rng: &mut StdRng, invalid: bool) -> String {
+        match self {
+            Identifier::Card(brand) => {
+                let brands = [CardBrand::Visa, CardBrand::Mastercard, CardBrand::Amex, CardBrand::Discover];
+                let brand = brand.unwrap_or_else(|| brands[rng.random_range(0..brands.len())]);
+                let numbers = brand.test_numbers();
+                let mut number = numbers[rng.random_range(0..numbers.len())].to_string();
+                if invalid {
+                    let check = number.pop().and_then(|digit| digit.to_digit(10)).unwrap_or(0);
+                    number.push(wrong_digit(rng, check, true));
+                }
+                number
+            }
+            Identifier::Iban(country) => {
+                let layout = IBAN_COUNTRIES.iter().find(|(code, _)| *code == country).map_or("", |(_, layout)| layout);
+                let account: String = layout.chars().map(|c| match c {
+                    'A' => char::from(b'A' + rng.random_range(0..26)),
+                    _ => random_digit(rng),
+                }).collect();
+                let valid = iban_check_digits(country, &account);
+                let mut check = valid;
+                // the check is mod 97, so e.g. 99 passes where 02 does
+                while invalid && check % 97 == valid % 97 {
+                    check = rng.random_range(0..100);
+                }
+                format!("{country}{check:02}{account}")
+            }
+            Identifier::Isbn13 | Identifier::Ean13 => {
+                let mut number = match self {
+                    Identifier::Isbn13 => ["978", "979"][rng.random_range(0..2)].to_string(),
+                    _ => String::new(),
+                };
+                while number.len() < 12 {
+                    number.push(random_digit(rng));
+                }
+                let check = ean_check_digit(&number);
+                number.push(wrong_digit(rng, check, invalid));
+                number
+            }
+        }
+    }
+}
+
+fn random_digit(rng: &mut StdRng) -> char {
+    char::from(b'0' + rng.random_range(0..10))
+}
+
+/// `check`, or any other digit if `invalid`
+fn wrong_digit(rng: &mut StdRng, check: u32, invalid: bool) -> char {
+    let digit = if invalid { (check + rng.random_range(1..10)) % 10 } else { check };
+    char::from_digit(digit, 10).unwrap_or('0')
+}
+
+/// EAN-13 check digit of the first 12 digits, which ISBN-13 uses too
+pub(crate) fn ean_check_digit(digits: &str) -> u32 {
+    let sum: u32 = digits.chars().filter_map(|c| c.to_digit(10)).enumerate()
+        .map(|(i, digit)| if i % 2 == 0 { digit } else { digit * 3 })
+        .sum();
+    (10 - sum % 10) % 10
+}
+
+/// ISO 7064 mod 97-10 check digits of an IBAN, from 2 to 98
+pub(crate) fn iban_check_digits(country: &str, account: &str) -> u32 {
+    // the account, the country and `00`, with letters as numbers from 10 for A, taken mod 97
+    let remainder = account.chars().chain(country.chars()).chain("00".chars()).fold(0u32, |remainder, c| {
+        let value = c.to_digit(36).unwrap_or(0);
+        match value {
+            0..=9 => (remainder * 10 + value) % 97,
+            _ => (remainder * 100 + value) % 97,
+        }
+    });
+    98 - remainder
+}
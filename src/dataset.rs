@@ -0,0 +1,78 @@
+use std::{io, path::{Path, PathBuf}, sync::Arc};
+
+use rand::{Rng, rngs::StdRng, prelude::SliceRandom};
+
+use crate::{ConfigError, parser::RowOrder};
+
+/// rows of a csv file bound with `csv(...)`. only the mapped columns are kept, in the order they
+/// were mapped
+#[derive(Debug, Clone, Default)]
+pub struct Dataset {
+    path: PathBuf,
+    rows: Arc<Vec<Vec<String>>>,
+}
+
+impl PartialEq for Dataset {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+}
+
+impl Dataset {
+    /// dataset for `path`, without any rows until it's loaded
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, rows: Arc::default() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// reads the csv from `base`, keeping `columns`. the header has to name every column and at
+    /// least one row has to follow it
+    pub(crate) fn load(&mut self, base: &Path, columns: &[&str]) -> Result<(), ConfigError> {
+        self.path = base.join(&self.path);
+        let io_error = |source| ConfigError::Io { path: self.path.clone(), source };
+        let mut reader = csv::Reader::from_path(&self.path).map_err(|e| io_error(e.into()))?;
+        let headers = reader.headers().map_err(|e| io_error(e.into()))?.clone();
+        let indices = columns.iter().map(|column| {
+            headers.iter().position(|header| header == *column).ok_or_else(|| io_error(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("no column named `{column}`"),
+            )))
+        }).collect::<Result<Vec<_>, _>>()?;
+
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            let record = record.map_err(|e| io_error(e.into()))?;
+            rows.push(indices.iter().map(|&i| record.get(i).unwrap_or_default().to_string()).collect());
+        }
+        if rows.is_empty() {
+            return Err(io_error(io::Error::new(io::ErrorKind::InvalidData, "file has no rows")));
+        }
+        self.rows = Arc::new(rows);
+        Ok(())
+    }
+
+    /// puts the rows in a random order, used for `unique` so the order is fixed for a whole run
+    pub(crate) fn shuffle(&mut self, rng: &mut StdRng) {
+        Arc::make_mut(&mut self.rows).shuffle(rng);
+    }
+
+    /// row for form number `index`, or nothing once a `unique` dataset has run out
+    pub(crate) fn row(&self, order: RowOrder, index: u64, rng: &mut StdRng) -> Option<&[String]> {
+        if self.rows.is_empty() {
+            return None;
+        }
+        let row = match order {
+            RowOrder::Sequential => (index % self.rows.len() as u64) as usize,
+            RowOrder::Random => rng.random_range(0..self.rows.len()),
+            RowOrder::Unique => usize::try_from(index).ok().filter(|&i| i < self.rows.len())?,
+        };
+        self.rows.get(row).map(Vec::as_slice)
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt::Write;
+
+use chrono::{NaiveDateTime, NaiveTime, TimeDelta, Timelike};
+use rand::{Rng, rngs::StdRng};
+
+use crate::parser::DateTimeBound;
+
+const SECONDS_PER_DAY: u32 = 86400;
+
+impl DateTimeBound {
+    /// pins a `now` relative bound to `now`, so every form of a run uses the same range
+    pub(crate) fn resolve(&mut self, now: NaiveDateTime) {
+        if let DateTimeBound::FromNow(offset) = self {
+            *self = DateTimeBound::At(now.checked_add_signed(*offset).unwrap_or(now));
+        }
+    }
+
+    /// the bound, which has to be pinned by `resolve` first if it's relative to `now`. reading
+    /// the clock here instead would make forms depend on when they're generated
+    pub(crate) fn at(&self) -> NaiveDateTime {
+        match self {
+            DateTimeBound::At(at) => *at,
+            DateTimeBound::FromNow(_) => panic!("`now` relative date bound used before the config was loaded"),
+        }
+    }
+}
+
+/// random day in min..=max. loading the config makes sure the bounds are in order, an empty
+/// range always gives min
+pub(crate) fn random_date(rng: &mut StdRng, min: &DateTimeBound, max: &DateTimeBound, format: &str) -> String {
+    let (min, max) = (min.at().date(), max.at().date());
+    let days = (max - min).num_days().max(0);
+    let day = min + TimeDelta::days(rng.random_range(0..=days));
+    day.and_time(NaiveTime::MIN).format(format).to_string()
+}
+
+/// random second in min..=max, with an empty range giving min like `random_date`
+pub(crate) fn random_datetime(rng: &mut StdRng, min: &DateTimeBound, max: &DateTimeBound, format: &str) -> String {
+    let (min, max) = (min.at(), max.at());
+    let seconds = (max - min).num_seconds().max(0);
+    (min + TimeDelta::seconds(rng.random_range(0..=seconds))).format(format).to_string()
+}
+
+/// random second in min..=max. if max is before min the range wraps past midnight, so
+/// "22:00" to "02:00" covers the night
+pub(crate) fn random_time(rng: &mut StdRng, min: NaiveTime, max: NaiveTime, format: &str) -> String {
+    let start = min.num_seconds_from_midnight();
+    let span = (max.num_seconds_from_midnight() + SECONDS_PER_DAY - start) % SECONDS_PER_DAY;
+    let seconds = (start + rng.random_range(0..=span)) % SECONDS_PER_DAY;
+    let time = NaiveTime::from_num_seconds_from_midnight_opt(seconds, 0).unwrap_or(min);
+    // formatted as a datetime so date fields in the format don't make chrono panic
+    NaiveDateTime::default().date().and_time(time).format(format).to_string()
+}
+
+/// specifiers replaced to make a date or time that doesn't exist, and the ones a format needs for
+/// the result to be impossible
+pub(crate) struct Impossible {
+    overrides: &'static [(char, &'static str)],
+    /// the format needs a specifier out of each group, a month on its own can't be impossible
+    needs: &'static [&'static [char]],
+}
+
+/// turns a date into February 30th
+pub(crate) const IMPOSSIBLE_DATE: Impossible = Impossible {
+    overrides: &[('d', "30"), ('e', "30"), ('m', "02"), ('b', "Feb"), ('h', "Feb"), ('B', "February")],
+    needs: &[&['d', 'e'], &['m', 'b', 'h', 'B']],
+};
+
+/// turns a time into 25:61:61
+pub(crate) const IMPOSSIBLE_TIME: Impossible = Impossible {
+    overrides: &[('H', "25"), ('k', "25"), ('I', "13"), ('l', "13"), ('M', "61"), ('S', "61")],
+    needs: &[&['H', 'k', 'I', 'l'], &['M']],
+};
+
+/// formats `at` with `format`, but writes the given text for the specifiers `impossible`
+/// overrides, so values chrono can't represent such as 02/30 can be produced. gives nothing if
+/// `format` doesn't use the specifiers that make the value impossible
+pub(crate) fn format_overriding(at: NaiveDateTime, format: &str, impossible: &Impossible) -> Option<String> {
+    // specifiers that stand for several others
+    let format = format
+        .replace("%F", "%Y-%m-%d")
+        .replace("%D", "%m/%d/%y")
+        .replace("%T", "%H:%M:%S")
+        .replace("%R", "%H:%M");
+    let mut out = String::new();
+    let mut overridden = Vec::new();
+    let mut chars = format.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        // a padding modifier, then the specifier itself
+        let mut end = start + 1;
+        if let Some(&(_, '-' | '_' | '0')) = chars.peek() {
+            chars.next();
+            end += 1;
+        }
+        let Some((_, spec)) = chars.next() else { break };
+        end += spec.len_utf8();
+        match impossible.overrides.iter().find(|(overridden, _)| *overridden == spec) {
+            Some((_, text)) => {
+                out.push_str(text);
+                overridden.push(spec);
+            }
+            None => {
+                let spec = &format[start..end];
+                if write!(out, "{}", at.format(spec)).is_err() {
+                    out.push_str(spec);
+                }
+            }
+        }
+    }
+    impossible.needs.iter()
+        .all(|group| group.iter().any(|spec| overridden.contains(spec)))
+        .then_some(out)
+}
@@ -0,0 +1,187 @@
+use std::{fs, io, path::{Path, PathBuf}, sync::Arc};
+
+use rand::{Rng, rngs::StdRng, distr::weighted::WeightedIndex};
+use rand_distr::{Distribution as _, Exp, Normal, Zipf};
+
+use crate::{ConfigError, parser::Distribution};
+
+/// how many times a sample that fell outside min..=max is redrawn before it's clamped instead
+const MAX_REDRAWS: usize = 16;
+
+impl Distribution {
+    /// draws a number in min..=max
+    pub(crate) fn sample(&self, rng: &mut StdRng, min: f64, max: f64) -> f64 {
+        if min >= max {
+            return min;
+        }
+        let draw = |rng: &mut StdRng| match self {
+            Distribution::Uniform => rng.random_range(min..=max),
+            Distribution::Normal { mean, sd } => Normal::new(*mean, *sd)
+                .map_or(*mean, |normal| normal.sample(rng)),
+            // shifted so it also works for ranges that include zero or negative numbers
+            Distribution::LogUniform => {
+                let upper = (max - min + 1.0).ln();
+                min + rng.random_range(0.0..=upper).exp() - 1.0
+            }
+            Distribution::Exponential { mean } => min + Exp::new(1.0 / mean)
+                .map_or(0.0, |exp| exp.sample(rng)),
+            Distribution::Fixed(value) => *value,
+            // rank 1 is min, rank 2 is min + 1 and so on
+            Distribution::Zipf { exponent } => min - 1.0 + Zipf::new((max - min).floor() + 1.0, *exponent)
+                .map_or(1.0, |zipf| zipf.sample(rng)),
+            Distribution::Histogram(histogram) => histogram.sample(rng).unwrap_or(min),
+        };
+        for _ in 0..MAX_REDRAWS {
+            let value = draw(rng);
+            if (min..=max).contains(&value) {
+                return value;
+            }
+        }
+        draw(rng).clamp(min, max)
+    }
+
+    /// draws a whole number in min..=max
+    pub(crate) fn sample_int(&self, rng: &mut StdRng, min: i64, max: i64) -> i64 {
+        match self {
+            Distribution::Uniform => rng.random_range(min..=max),
+            _ => (self.sample(rng, min as f64, max as f64).round() as i64).clamp(min, max),
+        }
+    }
+
+    /// draws a length in min..=max. parsing makes sure min <= max, so a length never goes past max
+    pub(crate) fn sample_len(&self, rng: &mut StdRng, min: usize, max: usize) -> usize {
+        match self {
+            Distribution::Uniform => rng.random_range(min..=max),
+            _ => (self.sample(rng, min as f64, max as f64).round() as usize).clamp(min, max),
+        }
+    }
+
+    /// reads the file of a histogram from `base`
+    pub(crate) fn load(&mut self, base: &Path) -> Result<(), ConfigError> {
+        match self {
+            Distribution::Histogram(histogram) => histogram.load(base),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// values and their weights read from the file of a `histogram("path")`, one `value,weight` per
+/// line. a value can also be a `low..high` range, which is drawn from uniformly
+#[derive(Debug, Clone, Default)]
+pub struct Histogram {
+    path: PathBuf,
+    buckets: Arc<Buckets>,
+}
+
+#[derive(Debug, Default)]
+struct Buckets {
+    ranges: Vec<(f64, f64)>,
+    /// picks a range by its weight, missing until the file is loaded
+    weights: Option<WeightedIndex<f64>>,
+}
+
+impl PartialEq for Histogram {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+}
+
+impl Histogram {
+    /// histogram for `path`, without buckets until it's loaded
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, buckets: Arc::default() }
+    }
+
+    /// reads the file. empty lines and lines starting with `#` are skipped
+    pub(crate) fn load(&mut self, base: &Path) -> Result<(), ConfigError> {
+        self.path = base.join(&self.path);
+        let invalid = |message: String| ConfigError::Io {
+            path: self.path.clone(),
+            source: io::Error::new(io::ErrorKind::InvalidData, message),
+        };
+        let contents = fs::read_to_string(&self.path)
+            .map_err(|source| ConfigError::Io { path: self.path.clone(), source })?;
+
+        let mut ranges = Vec::new();
+        let mut weights = Vec::new();
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (range, weight) = parse_bucket(line)
+                .ok_or_else(|| invalid(format!("line {}: expected `value,weight` or `low..high,weight`", i + 1)))?;
+            ranges.push(range);
+            weights.push(weight);
+        }
+        let weights = WeightedIndex::new(&weights)
+            .map_err(|_| invalid("histogram needs at least one value with a weight larger than 0".to_string()))?;
+        self.buckets = Arc::new(Buckets { ranges, weights: Some(weights) });
+        Ok(())
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> Option<f64> {
+        let (low, high) = self.buckets.ranges[self.buckets.weights.as_ref()?.sample(rng)];
+        Some(rng.random_range(low..=high))
+    }
+}
+
+/// `value,weight` or `low..high,weight`, with low <= high and a weight no smaller than 0
+fn parse_bucket(line: &str) -> Option<((f64, f64), f64)> {
+    let (value, weight) = line.split_once(',')?;
+    let number = |text: &str| text.trim().parse::<f64>().ok().filter(|n| n.is_finite());
+    let range = match value.split_once("..") {
+        Some((low, high)) => (number(low)?, number(high)?),
+        None => (number(value)?, number(value)?),
+    };
+    let weight = number(weight).filter(|weight| *weight >= 0.0)?;
+    (range.0 <= range.1).then_some((range, weight))
+}
+
+/// number of digits after the decimal point used for amounts in `currency` (an ISO 4217 code)
+pub(crate) fn currency_decimals(currency: &str) -> usize {
+    match currency.to_ascii_uppercase().as_str() {
+        "JPY" | "KRW" | "VND" | "CLP" | "ISK" | "UGX" | "XAF" | "XOF" | "PYG" => 0,
+        "BHD" | "KWD" | "OMR" | "JOD" | "TND" | "IQD" | "LYD" => 3,
+        _ => 2,
+    }
+}
+
+/// whether some number with `precision` decimals lies in min..=max
+pub(crate) fn holds_decimal(min: f64, max: f64, precision: usize) -> bool {
+    decimal_at_least(min, 10f64.powi(precision as i32)) <= max
+}
+
+/// smallest number with the decimals of `scale` that's no smaller than `min`
+fn decimal_at_least(min: f64, scale: f64) -> f64 {
+    let steps = (min * scale).round();
+    if !steps.is_finite() {
+        return min;
+    }
+    if steps / scale < min { (steps + 1.0) / scale } else { steps / scale }
+}
+
+/// largest number with the decimals of `scale` that's no larger than `max`
+fn decimal_at_most(max: f64, scale: f64) -> f64 {
+    let steps = (max * scale).round();
+    if !steps.is_finite() {
+        return max;
+    }
+    if steps / scale > max { (steps - 1.0) / scale } else { steps / scale }
+}
+
+/// formats `value` with `precision` decimals without going outside min..=max through rounding.
+/// if no number with that many decimals fits, `value` is written out in full instead
+pub(crate) fn format_decimal(value: f64, precision: usize, min: f64, max: f64) -> String {
+    let scale = 10f64.powi(precision as i32);
+    let mut rounded = (value * scale).round() / scale;
+    if rounded > max {
+        rounded = decimal_at_most(max, scale);
+    } else if rounded < min {
+        rounded = decimal_at_least(min, scale);
+    }
+    if !(min..=max).contains(&rounded) {
+        return value.clamp(min, max).to_string();
+    }
+    format!("{rounded:.precision$}")
+}
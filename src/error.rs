@@ -0,0 +1,68 @@
+use std::{fmt, io, path::{Path, PathBuf}};
+
+/// Error raised while loading a form config file.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// the config file couldn't be read. a file a generator refers to that can't be loaded is a
+    /// syntax error pointing at the generator
+    Io { path: PathBuf, source: io::Error },
+
+    /// the config file was read but doesn't follow the grammar.
+    /// line and column are 1 based, snippet is the full offending line
+    Syntax {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        snippet: String,
+        expected: String,
+    },
+}
+
+impl ConfigError {
+    /// builds a syntax error for byte `offset` of `source`, working out the line, column and snippet
+    pub(crate) fn syntax(path: &Path, source: &str, offset: usize, expected: String) -> Self {
+        let offset = offset.min(source.len());
+        let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[offset..].find('\n').map_or(source.len(), |i| offset + i);
+        ConfigError::Syntax {
+            path: path.to_path_buf(),
+            line: source[..offset].matches('\n').count() + 1,
+            column: source[line_start..offset].chars().count() + 1,
+            snippet: source[line_start..line_end].trim_end_matches('\r').to_string(),
+            expected,
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, source } => {
+                write!(f, "failed to read {}: {source}", path.display())
+            }
+            ConfigError::Syntax { path, line, column, snippet, expected } => {
+                // keep tabs in the caret line so it still lines up with the snippet
+                let padding: String = snippet
+                    .chars()
+                    .take(column - 1)
+                    .map(|c| if c == '\t' { '\t' } else { ' ' })
+                    .collect();
+                let gutter = " ".repeat(line.to_string().len());
+                writeln!(f, "error: expected {expected}")?;
+                writeln!(f, "{gutter}--> {}:{line}:{column}", path.display())?;
+                writeln!(f, "{gutter} |")?;
+                writeln!(f, "{line} | {snippet}")?;
+                write!(f, "{gutter} | {padding}^")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io { source, .. } => Some(source),
+            ConfigError::Syntax { .. } => None,
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use rand::Rng;
+
+/// text around the number of a `seq(...)`, written like a rust format string: `INV-{:06}` pads
+/// to 6 digits with zeros, `{:6}` with spaces, and `{}` doesn't pad
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NumberFormat {
+    prefix: String,
+    width: usize,
+    zero_pad: bool,
+    suffix: String,
+}
+
+impl NumberFormat {
+    /// reads a format with exactly one placeholder. `{{` and `}}` are literal braces
+    pub fn parse(format: &str) -> Option<Self> {
+        let mut parts = [String::new(), String::new()];
+        let mut spec = None;
+        let mut chars = format.chars().peekable();
+        while let Some(c) = chars.next() {
+            let part = &mut parts[spec.is_some() as usize];
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    part.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    part.push('}');
+                }
+                '{' if spec.is_none() => {
+                    let mut inner = String::new();
+                    loop {
+                        match chars.next()? {
+                            '}' => break,
+                            c => inner.push(c),
+                        }
+                    }
+                    spec = Some(inner);
+                }
+                '{' | '}' => return None,
+                c => part.push(c),
+            }
+        }
+        let spec = spec?;
+        let (zero_pad, width) = match spec.strip_prefix(':') {
+            None if spec.is_empty() => (false, 0),
+            None => return None,
+            Some(width) => (width.starts_with('0'), width.parse().ok()?),
+        };
+        let [prefix, suffix] = parts;
+        Some(Self { prefix, width, zero_pad, suffix })
+    }
+
+    pub fn format(&self, number: i64) -> String {
+        let (prefix, width, suffix) = (&self.prefix, self.width, &self.suffix);
+        match self.zero_pad {
+            true => format!("{prefix}{number:0width$}{suffix}"),
+            false => format!("{prefix}{number:width$}{suffix}"),
+        }
+    }
+}
+
+/// number of the request with number `index` for a counter from `start` in steps of `step`, if
+/// it's within the 64 bit range
+pub(crate) fn counter_value(start: i64, step: i64, index: u64) -> Option<i64> {
+    i64::try_from(index).ok()?.checked_mul(step)?.checked_add(start)
+}
+
+/// number of requests a counter can number before it would leave the 64 bit range, if they don't
+/// cover every request number anyway
+pub(crate) fn counter_limit(start: i64, step: i64) -> Option<u64> {
+    let room = match step {
+        0 => return None,
+        1.. => i64::MAX as i128 - start as i128,
+        _ => start as i128 - i64::MIN as i128,
+    };
+    u64::try_from(room / (step as i128).abs() + 1).ok()
+}
+
+/// layout of a `uuid(...)`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UuidVersion {
+    /// all random
+    V4,
+    /// milliseconds since the unix epoch followed by random bits, so ids sort by creation time
+    V7,
+}
+
+impl UuidVersion {
+    /// a uuid in the usual 8-4-4-4-12 hex form. the time of a v7 comes from the clock
+    pub(crate) fn generate(self, rng: &mut impl Rng) -> String {
+        let mut bits: u128 = rng.random();
+        if self == UuidVersion::V7 {
+            let millis = chrono::Utc::now().timestamp_millis().max(0) as u128;
+            bits = (millis & 0xffff_ffff_ffff) << 80 | bits & ((1 << 80) - 1);
+        }
+        let version = match self {
+            UuidVersion::V4 => 4,
+            UuidVersion::V7 => 7,
+        };
+        // the version goes in bits 76..80 and the rfc 9562 variant `10` in bits 62..64
+        bits = bits & !(0xf << 76) | version << 76;
+        bits = bits & !(0b11 << 62) | 0b10 << 62;
+        let hex = format!("{bits:032x}");
+        format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+    }
+}
@@ -0,0 +1,322 @@
+use std::fmt;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use rand::{Rng, rngs::StdRng, seq::{IndexedRandom, SliceRandom}};
+
+use crate::{FormFields, Parameter, Scope, Transform, Value, datetime, distribution};
+
+/// kind of constraint a form breaks on purpose when forms are generated with invalid values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ViolationKind {
+    /// string or name longer than its max length
+    OverLength,
+    MalformedEmail,
+    /// day that doesn't exist, such as 02/30
+    ImpossibleDate,
+    /// time of day such as 25:61
+    ImpossibleTime,
+    /// phone number with letters in place of digits
+    NonNumericPhone,
+    /// number below min or above max
+    OutOfRange,
+    /// password shorter than its min length or without a character its policy requires
+    WeakPassword,
+    /// card number, IBAN, ISBN or EAN with wrong check digits
+    BadChecksum,
+    /// a field left out of the form
+    MissingField,
+    /// a field sent twice
+    DuplicatedField,
+}
+
+impl fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ViolationKind::OverLength => "over-length value",
+            ViolationKind::MalformedEmail => "malformed email",
+            ViolationKind::ImpossibleDate => "impossible date",
+            ViolationKind::ImpossibleTime => "impossible time",
+            ViolationKind::NonNumericPhone => "non-numeric phone",
+            ViolationKind::OutOfRange => "out of range number",
+            ViolationKind::WeakPassword => "weak password",
+            ViolationKind::BadChecksum => "bad checksum",
+            ViolationKind::MissingField => "missing field",
+            ViolationKind::DuplicatedField => "duplicated field",
+        })
+    }
+}
+
+/// the one invalid input a form contains
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Violation {
+    pub kind: ViolationKind,
+    /// name of the field that is invalid
+    pub field: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} in `{}`", self.kind, self.field)
+    }
+}
+
+/// how many numbers past min or max an out of range number can be
+const OUT_OF_RANGE_STEPS: i64 = 100;
+
+impl Parameter {
+    /// the kind of invalid value this generator can produce, if it can. a generator that wraps
+    /// others can if one of them can, and gives the kind of the first that can
+    pub(crate) fn violation(&self) -> Option<ViolationKind> {
+        match self {
+            Parameter::Maybe { param, .. }
+            | Parameter::Repeat { param, .. }
+            | Parameter::Unique { param, .. }
+            | Parameter::Let { param, .. } => param.violation(),
+            Parameter::Choose { options, .. } => options.iter().find_map(Parameter::violation),
+            Parameter::Weighted { options } => options.iter().find_map(|(_, option)| option.violation()),
+            // a template is free text, a broken generator inside it doesn't break the field
+            Parameter::Template { .. } => None,
+            Parameter::String { .. } | Parameter::Name { .. } => Some(ViolationKind::OverLength),
+            Parameter::Email { .. } => Some(ViolationKind::MalformedEmail),
+            Parameter::CellPhone { .. } | Parameter::Phone { .. } => Some(ViolationKind::NonNumericPhone),
+            Parameter::Date { format, .. } | Parameter::DateTime { format, .. } => {
+                datetime::format_overriding(NaiveDateTime::default(), format, &datetime::IMPOSSIBLE_DATE)
+                    .map(|_| ViolationKind::ImpossibleDate)
+            }
+            Parameter::Time { format, .. } => {
+                datetime::format_overriding(NaiveDateTime::default(), format, &datetime::IMPOSSIBLE_TIME)
+                    .map(|_| ViolationKind::ImpossibleTime)
+            }
+            Parameter::Int { min, max, .. } => {
+                (*min > i64::MIN || *max < i64::MAX).then_some(ViolationKind::OutOfRange)
+            }
+            Parameter::Float { min, max, precision, .. } => {
+                leaves_range(*min, *max, *precision).then_some(ViolationKind::OutOfRange)
+            }
+            Parameter::Money { min, max, currency, .. } => {
+                leaves_range(*min, *max, distribution::currency_decimals(currency)).then_some(ViolationKind::OutOfRange)
+            }
+            // an identifier that's always invalid can't be made any more so
+            Parameter::Checksum { invalid: false, .. } => Some(ViolationKind::BadChecksum),
+            Parameter::Password { min, policy, .. } => {
+                (*min > 1 || policy.min_len() > 0).then_some(ViolationKind::WeakPassword)
+            }
+            _ => None,
+        }
+    }
+
+    /// names of the fields this generator sends in every form, each once
+    fn sent_once(&self) -> Vec<&str> {
+        match self {
+            Parameter::Maybe { .. }
+            | Parameter::Repeat { .. }
+            | Parameter::Choose { .. }
+            | Parameter::Weighted { .. }
+            | Parameter::CheckBoxes { .. }
+            | Parameter::Let { .. } => Vec::new(),
+            Parameter::Unique { param, .. } => param.sent_once(),
+            _ => self.field_names(),
+        }
+    }
+
+    /// generates the value of a generator that `violation` gave a kind for, breaking its constraint,
+    /// along with what was broken. a wrapper breaks one of the generators it wraps and runs it even
+    /// where it would otherwise leave it out, e.g. in a `maybe(...)` or an option `one_of` didn't pick
+    pub(crate) fn gen_invalid(&self, rng: &mut StdRng, scope: &Scope) -> (FormFields, Option<Violation>) {
+        let value = match self {
+            // a `unique` doesn't remember the broken value, it's no value of its generator
+            Parameter::Maybe { param, .. }
+            | Parameter::Unique { param, .. }
+            | Parameter::Let { param, .. } => return param.gen_invalid(rng, scope),
+            Parameter::Repeat { min, max, param } => {
+                let count = rng.random_range(*min..=*max).max(1);
+                let broken = rng.random_range(0..count);
+                let mut fields = FormFields::new();
+                let mut violation = None;
+                for i in 0..count {
+                    if i == broken {
+                        let (invalid, broke) = param.gen_invalid(rng, scope);
+                        fields.extend(invalid);
+                        violation = broke;
+                    } else {
+                        fields.extend(param.gen_params(rng, scope));
+                    }
+                }
+                return (fields, violation);
+            }
+            Parameter::Choose { n, options } => {
+                let breakable: Vec<usize> = (0..options.len()).filter(|i| options[*i].violation().is_some()).collect();
+                let Some(&target) = breakable.choose(rng) else { return (self.gen_params(rng, scope), None) };
+                if *n == 0 {
+                    // a valid form sends none of the options, so the broken one goes on its own
+                    return options[target].gen_invalid(rng, scope);
+                }
+                let mut picked: Vec<usize> = (0..options.len()).filter(|i| *i != target).collect();
+                picked.shuffle(rng);
+                picked.truncate(n.saturating_sub(1));
+                picked.insert(rng.random_range(0..=picked.len()), target);
+                let mut fields = FormFields::new();
+                let mut violation = None;
+                for i in picked {
+                    if i == target {
+                        let (invalid, broke) = options[i].gen_invalid(rng, scope);
+                        fields.extend(invalid);
+                        violation = broke;
+                    } else {
+                        fields.extend(options[i].gen_params(rng, scope));
+                    }
+                }
+                return (fields, violation);
+            }
+            Parameter::Weighted { options } => {
+                let breakable: Vec<&Parameter> = options.iter().map(|(_, option)| option).filter(|option| option.violation().is_some()).collect();
+                return match breakable.choose(rng) {
+                    Some(option) => option.gen_invalid(rng, scope),
+                    None => (self.gen_params(rng, scope), None),
+                };
+            }
+            Parameter::String { max_len, .. } => over_length(rng, *max_len, true),
+            Parameter::Name { max_len, .. } => {
+                let first = over_length(rng, *max_len, false);
+                let last = over_length(rng, *max_len, false);
+                format!("{first} {last}")
+            }
+            Parameter::Email { domains, .. } => {
+                let len = rng.random_range(5..15);
+                let user = random_text(rng, len, false).to_lowercase();
+                let domain = &domains[rng.random_range(0..domains.len())];
+                match rng.random_range(0..8) {
+                    0 => user,
+                    1 => format!("{user}@"),
+                    2 => format!("@{domain}"),
+                    3 => format!("{user}@@{domain}"),
+                    4 => format!("{user} @{domain}"),
+                    5 => format!("{user}@.{domain}"),
+                    6 => format!("{user}..{user}@{domain}"),
+                    _ => format!("{user}@{domain}@{domain}"),
+                }
+            }
+            Parameter::CellPhone { .. } => {
+                let letters = |rng: &mut StdRng, len| random_text(rng, len, false).to_lowercase();
+                format!("({}) {}-{}", letters(rng, 3), letters(rng, 3), letters(rng, 4))
+            }
+            Parameter::Phone { locale, .. } => {
+                let len = rng.random_range(6..10);
+                format!("+{}{}", locale.calling_code(), random_text(rng, len, false).to_lowercase())
+            }
+            Parameter::Date { format, .. } | Parameter::DateTime { format, .. } => {
+                let at = NaiveDate::from_ymd_opt(rng.random_range(1970..2030), 2, 28)
+                    .and_then(|day| day.and_hms_opt(rng.random_range(0..24), rng.random_range(0..60), 0))
+                    .unwrap_or_default();
+                datetime::format_overriding(at, format, &datetime::IMPOSSIBLE_DATE).unwrap_or_default()
+            }
+            Parameter::Time { format, .. } => {
+                let at = NaiveDateTime::default().date().and_time(NaiveTime::MIN);
+                datetime::format_overriding(at, format, &datetime::IMPOSSIBLE_TIME).unwrap_or_default()
+            }
+            Parameter::Int { min, max, .. } => {
+                let steps = rng.random_range(1..=OUT_OF_RANGE_STEPS);
+                let below = min.checked_sub(steps);
+                let above = max.checked_add(steps);
+                match (below, above) {
+                    (Some(below), Some(above)) => if rng.random_bool(0.5) { below } else { above },
+                    (below, above) => below.or(above).unwrap_or(*max),
+                }.to_string()
+            }
+            Parameter::Float { min, max, precision, .. } => out_of_range(rng, *min, *max, *precision),
+            Parameter::Money { min, max, currency, .. } => {
+                out_of_range(rng, *min, *max, distribution::currency_decimals(currency))
+            }
+            Parameter::Checksum { kind, .. } => kind.generate(rng, true),
+            Parameter::Password { min, max, policy, .. } => policy.generate_weak(rng, *min, *max).unwrap_or_default(),
+            _ => return (self.gen_params(rng, scope), None),
+        };
+        let Some(name) = self.field_names().first().map(|name| name.to_string()) else { return (FormFields::new(), None) };
+        let violation = self.violation().map(|kind| Violation { kind, field: name.clone() });
+        (vec![(name, value.into())], violation)
+    }
+}
+
+/// name of the field after the `let` at `params[i]` that sends its value, as it is or with its case
+/// changed, in every form. a broken value that no field sends that way doesn't make a form invalid
+pub(crate) fn copied_by(params: &[Parameter], i: usize) -> Option<&str> {
+    let Parameter::Let { var, .. } = &params[i] else { return None };
+    params[i + 1..].iter().find_map(|param| match param {
+        Parameter::Static { name, val: Value::Ref { name: copied, transform } }
+            if copied == var && *transform != Transform::Slug => Some(name.as_str()),
+        _ => None,
+    })
+}
+
+/// text longer than `max_len`, up to about twice as long
+fn over_length(rng: &mut StdRng, max_len: usize, punctuation: bool) -> String {
+    let len = rng.random_range(max_len.saturating_add(1)..=max_len.saturating_mul(2).saturating_add(16));
+    random_text(rng, len, punctuation)
+}
+
+fn random_text(rng: &mut StdRng, len: usize, punctuation: bool) -> String {
+    let chars: &[u8] = if punctuation {
+        b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789., "
+    } else {
+        b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ"
+    };
+    (0..len).map(|_| chars[rng.random_range(0..chars.len())] as char).collect()
+}
+
+/// a number 1 to `OUT_OF_RANGE_STEPS` steps of the last printed decimal below min or above max
+fn out_of_range(rng: &mut StdRng, min: f64, max: f64, precision: usize) -> String {
+    let steps = rng.random_range(1..=OUT_OF_RANGE_STEPS);
+    match (past_bound(min, false, steps, precision), past_bound(max, true, steps, precision)) {
+        (Some(below), Some(above)) => if rng.random_bool(0.5) { below } else { above },
+        (below, above) => below.or(above).unwrap_or_default(),
+    }
+}
+
+/// whether some number printed with `precision` decimals lies outside min..=max
+fn leaves_range(min: f64, max: f64, precision: usize) -> bool {
+    past_bound(min, false, 1, precision).is_some() || past_bound(max, true, 1, precision).is_some()
+}
+
+/// `bound` moved `steps` of the last printed decimal up or down, printed with `precision`
+/// decimals, if that's a finite number that still reads as past the bound
+fn past_bound(bound: f64, above: bool, steps: i64, precision: usize) -> Option<String> {
+    let scale = 10f64.powi(precision as i32);
+    let offset = steps as f64 / scale;
+    let value = match above {
+        true => (bound * scale).ceil() / scale + offset,
+        false => (bound * scale).floor() / scale - offset,
+    };
+    let past = |value: f64| value.is_finite() && if above { value > bound } else { value < bound };
+    // a bound this large has no digits left after the point, or the step is lost in rounding
+    let value = match (past(value), above) {
+        (true, _) => value,
+        (false, true) => bound.next_up(),
+        (false, false) => bound.next_down(),
+    };
+    let text = format!("{value:.precision$}");
+    text.parse().ok().filter(|value| past(*value)).map(|_| text)
+}
+
+/// names of the fields the config sends in every form, exactly once. leaving out or repeating
+/// anything else, e.g. a checkbox or a field behind `maybe`, can happen in a valid form too
+pub(crate) fn required_fields(params: &[Parameter]) -> Vec<&str> {
+    let names: Vec<&str> = params.iter().flat_map(Parameter::field_names).collect();
+    params.iter()
+        .flat_map(Parameter::sent_once)
+        .filter(|name| names.iter().filter(|other| *other == name).count() == 1)
+        .collect()
+}
+
+/// leaves one of the `required` fields out of `fields`, or sends it twice, and gives its name
+pub(crate) fn break_fields(fields: &mut FormFields, required: &[&str], kind: ViolationKind, rng: &mut StdRng) -> Option<String> {
+    let present: Vec<usize> = (0..fields.len()).filter(|i| required.contains(&fields[*i].0.as_str())).collect();
+    let (name, value) = fields[*present.choose(rng)?].clone();
+    match kind {
+        ViolationKind::MissingField => fields.retain(|(field, _)| *field != name),
+        _ => {
+            let at = fields.iter().rposition(|(field, _)| *field == name).unwrap_or(0);
+            fields.insert(at + 1, (name.clone(), value));
+        }
+    }
+    Some(name)
+}
@@ -1,12 +1,43 @@
 pub mod scraper;
 pub mod spammer;
+pub mod multipart;
+pub mod preview;
+mod checksum;
+mod dataset;
+mod datetime;
+mod distribution;
+mod error;
+mod ids;
+mod invalid;
+mod loader;
+mod locale;
 mod parser;
-use crate::parser::parse_line;
+mod password;
+mod payload;
+mod regex_gen;
+mod text;
+mod unique;
+mod upload;
 
 pub use self::scraper as crtsh;
-pub use self::parser::Parameter;
+pub use self::error::ConfigError;
+pub use self::parser::{parse_config, SyntaxError, Parameter, FileSource, Distribution, DateTimeBound, Value, Transform, TemplatePart, RowOrder};
+pub use self::dataset::Dataset;
+pub use self::distribution::Histogram;
+pub use self::payload::PayloadSet;
+pub use self::unique::UniqueValues;
+pub use self::ids::{NumberFormat, UuidVersion};
+pub use self::locale::{AddressPart, Locale, NamePart};
+pub use self::text::{Corpus, TextStyle};
+pub use self::upload::Uploads;
+pub use self::password::PasswordPolicy;
+pub use self::checksum::{CardBrand, Identifier};
+pub use self::invalid::{Violation, ViolationKind};
+pub use self::regex_gen::Pattern;
+pub use self::multipart::Form;
+pub use self::preview::PreviewFormat;
 
-use std::{path::PathBuf, collections::HashMap, fs};
+use std::{cell::Cell, path::{Path, PathBuf}, collections::HashMap};
 
 use clap::{Parser, Subcommand};
 
@@ -54,76 +85,322 @@ pub enum Commands {
         
         /// max number of open requests to domain
         #[arg(short, long)]
-        max_open: usize
+        max_open: usize,
+        
+        /// shuffle the order of the parts in each form instead of following the config
+        #[arg(long)]
+        shuffle: bool,
+        
+        /// seed for the random generators. the same seed always produces the same form for a
+        /// given request number. a random seed is picked and printed if not given
+        #[arg(long)]
+        seed: Option<u64>,
+        
+        /// put one invalid value (a string past its max length, a malformed email, a missing
+        /// field, ..) in every request, and report which of them the server accepted
+        #[arg(long)]
+        invalid: bool
+    },
+    
+    /// print generated forms without sending them
+    Preview {
+        /// config file specifying how parameters are to be generated
+        #[arg(short, long)]
+        config: String,
+        
+        /// number of forms to generate
+        #[arg(short = 'n', long, default_value_t = 10)]
+        count: u64,
+        
+        /// request number of the first form, to reproduce a single request of a `spam` run
+        #[arg(long, default_value_t = 0)]
+        start: u64,
+        
+        /// how to print the forms
+        #[arg(short, long, value_enum, default_value_t = PreviewFormat::Json)]
+        format: PreviewFormat,
+        
+        /// shuffle the order of the parts in each form instead of following the config
+        #[arg(long)]
+        shuffle: bool,
+        
+        /// seed for the random generators, same meaning as for `spam`
+        #[arg(long)]
+        seed: Option<u64>,
+        
+        /// put one invalid value in every form, same meaning as for `spam`
+        #[arg(long)]
+        invalid: bool
+    }
+}
+
+/// value of a single form part
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Text(String),
+    File {filename: String, content_type: String, data: Vec<u8>},
+}
+
+impl From<String> for FieldValue {
+    fn from(text: String) -> Self {
+        FieldValue::Text(text)
+    }
+}
+
+/// generated form fields in the order they should be sent. names can repeat, e.g. for a group of
+/// checkboxes that all submit under the same name
+pub type FormFields = Vec<(String, FieldValue)>;
+
+use rand::{Rng, SeedableRng, rngs::StdRng, prelude::SliceRandom, seq::IndexedRandom, distr::{Distribution as _, weighted::WeightedIndex}};
+
+/// rng for the form with number `index` of a run seeded with `seed`.
+/// seed and index each fill their own part of the rng seed, so no two forms of a run share a stream
+pub fn form_rng(seed: u64, index: u64) -> StdRng {
+    let mut rng_seed = [0u8; 32];
+    rng_seed[..8].copy_from_slice(&seed.to_le_bytes());
+    rng_seed[8..16].copy_from_slice(&index.to_le_bytes());
+    StdRng::from_seed(rng_seed)
+}
+
+/// turns a parsed config into forms, the same way for `spam` and `preview`
+#[derive(Debug, Clone)]
+pub struct FormGenerator {
+    params: Vec<Parameter>,
+    seed: u64,
+    shuffle: bool,
+    invalid: bool,
+}
+
+impl FormGenerator {
+    pub fn new(mut params: Vec<Parameter>, seed: u64, shuffle: bool) -> Self {
+        // the order `unique` rows are used in only depends on the seed
+        let mut rng = StdRng::seed_from_u64(seed);
+        for param in &mut params {
+            if let Parameter::Csv { dataset, order: RowOrder::Unique, .. } = param {
+                dataset.shuffle(&mut rng);
+            }
+        }
+        Self { params, seed, shuffle, invalid: false }
+    }
+    
+    /// makes every form break exactly one constraint, e.g. a string past its max length or a
+    /// missing field, for testing the server's validation
+    pub fn with_invalid_values(mut self, invalid: bool) -> Self {
+        self.invalid = invalid;
+        self
+    }
+    
+    pub fn invalid_values(&self) -> bool {
+        self.invalid
+    }
+    
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    
+    /// number of forms that can be generated before a `unique` csv dataset runs out or a counter
+    /// would leave the 64 bit range, if there is such a limit
+    pub fn limit(&self) -> Option<u64> {
+        fn limit(param: &Parameter) -> Option<u64> {
+            let own = match param {
+                Parameter::Csv { dataset, order: RowOrder::Unique, .. } => Some(dataset.len() as u64),
+                Parameter::Counter { start, step, .. } => ids::counter_limit(*start, *step),
+                _ => None,
+            };
+            param.nested().into_iter().filter_map(limit).chain(own).min()
+        }
+        self.params.iter().filter_map(limit).min()
+    }
+    
+    /// builds form number `index` of the run. apart from fresh uuids and the time in v7 uuids the
+    /// result only depends on the seed and the index
+    pub fn generate(&self, index: u64) -> Form {
+        self.build(index).0
+    }
+    
+    /// like `generate`, but gives nothing if a `unique(...)` generator of this form has run out of
+    /// values instead of a form without them. forms with unique values depend on which forms were
+    /// generated before them in the run, not just on the seed and the index
+    pub fn try_generate(&self, index: u64) -> Option<Form> {
+        let (form, exhausted) = self.build(index);
+        (!exhausted).then_some(form)
+    }
+    
+    /// form number `index`, and whether a `unique(...)` generator found no unused value for it
+    fn build(&self, index: u64) -> (Form, bool) {
+        let mut rng = form_rng(self.seed, index);
+        let boundary = Form::random_boundary(&mut rng);
+        let (mut fields, violation, exhausted) = match self.invalid {
+            false => Parameter::gen_form(&self.params, index, &boundary, &mut rng, None),
+            true => self.generate_invalid(index, &boundary, &mut rng),
+        };
+        if self.shuffle {
+            fields.shuffle(&mut rng);
+        }
+        (Form { boundary, fields, violation }, exhausted)
+    }
+    
+    /// field names of a `unique(...)` generator that ran out of values, if one did
+    pub fn exhausted(&self) -> Option<String> {
+        self.params.iter().find_map(Parameter::exhausted)
+    }
+    
+    /// picks one of the generators that can produce an invalid value, or a required field to leave
+    /// out or send twice, and breaks it. a config with neither gives valid forms
+    fn generate_invalid(&self, index: u64, boundary: &str, rng: &mut StdRng) -> (FormFields, Option<Violation>, bool) {
+        let mut candidates: Vec<(Option<usize>, ViolationKind)> = self.params.iter().enumerate()
+            .filter_map(|(i, param)| {
+                let kind = param.violation()?;
+                // a let isn't sent, breaking it only shows in a field that copies it
+                if let Parameter::Let { .. } = param {
+                    invalid::copied_by(&self.params, i)?;
+                }
+                Some((Some(i), kind))
+            })
+            .collect();
+        let required = invalid::required_fields(&self.params);
+        if !required.is_empty() {
+            candidates.extend([(None, ViolationKind::MissingField), (None, ViolationKind::DuplicatedField)]);
+        }
+        let Some(&(target, kind)) = candidates.choose(rng) else {
+            return Parameter::gen_form(&self.params, index, boundary, rng, None);
+        };
+        
+        let (mut fields, violation, exhausted) = Parameter::gen_form(&self.params, index, boundary, rng, target);
+        let violation = match target {
+            Some(i) => match invalid::copied_by(&self.params, i) {
+                Some(field) => violation.map(|violation| Violation { field: field.to_string(), ..violation }),
+                None => violation,
+            },
+            None => {
+                let field = invalid::break_fields(&mut fields, &required, kind, rng);
+                field.map(|field| Violation { kind, field })
+            }
+        };
+        (fields, violation, exhausted)
+    }
+}
+
+/// what `ref(...)` can see while a form is generated: `let` bindings and the fields so far
+#[derive(Debug, Default)]
+struct Scope {
+    /// number of the form being generated
+    index: u64,
+    /// boundary the form is sent with, empty when it isn't known
+    boundary: String,
+    vars: HashMap<String, String>,
+    fields: FormFields,
+    /// set when a `unique(...)` generator found no unused value for this form
+    exhausted: Cell<bool>,
+}
+
+impl Scope {
+    /// value of a `let` binding, or else of the first text field called `name`
+    fn lookup(&self, name: &str) -> Option<&str> {
+        self.vars.get(name).map(String::as_str).or_else(|| {
+            self.fields.iter().find_map(|(field, value)| match value {
+                FieldValue::Text(text) if field == name => Some(text.as_str()),
+                _ => None,
+            })
+        })
+    }
+    
+    fn resolve(&self, value: &Value) -> String {
+        match value {
+            Value::Literal(text) => text.clone(),
+            Value::Ref { name, transform } => transform.apply(self.lookup(name).unwrap_or_default()),
+        }
     }
 }
 
-use rand::{Rng, rng, prelude::SliceRandom};
+impl Transform {
+    fn apply(self, value: &str) -> String {
+        match self {
+            Transform::None => value.to_string(),
+            Transform::Lower => value.to_lowercase(),
+            Transform::Upper => value.to_uppercase(),
+            Transform::Slug => {
+                let mut slug = String::new();
+                for c in value.chars() {
+                    if c.is_ascii_alphanumeric() {
+                        slug.push(c.to_ascii_lowercase());
+                    } else if !slug.is_empty() && !slug.ends_with('_') {
+                        slug.push('_');
+                    }
+                }
+                slug.trim_end_matches('_').to_string()
+            }
+        }
+    }
+}
 
 impl Parameter {
-    fn gen_params(&self) -> HashMap<String, String> {
-        let mut rng = rng();
-        let mut params = HashMap::new();
+    fn gen_params(&self, rng: &mut StdRng, scope: &Scope) -> FormFields {
+        let mut params = FormFields::new();
 
         match self.clone() {
-            Parameter::Email { name, domains } => {
+            Parameter::Email { name, domains, length } => {
                 let chars: Vec<char> = "abcdefghijklmnopqrstuvwxyz0123456789".chars().collect();
-                let len = rng.random_range(5..15);
+                let len = length.sample_len(rng, 5, 14);
                 let username: String = (0..len)
                     .map(|_| chars[rng.random_range(0..chars.len())])
                     .collect();
                 let domain = domains[rng.random_range(0..domains.len())].clone();
-                params.insert(name.clone(), format!("{}@{}", username, domain));
+                params.push((name.clone(), format!("{}@{}", username, domain).into()));
             }
 
-            Parameter::YesNo { name } => {
-                params.insert(name.clone(), if rng.random_bool(0.5) { "Yes" } else { "No" }.to_string());
+            Parameter::YesNo { name, probability } => {
+                params.push((name.clone(), if rng.random_bool(probability) { "Yes" } else { "No" }.to_string().into()));
             }
 
             Parameter::CellPhone { name } => {
                 let area_code = rng.random_range(100..999);
                 let middle = rng.random_range(100..999);
                 let end = rng.random_range(1000..9999);
-                params.insert(name.clone(), format!("({}) {}-{}", area_code, middle, end));
+                params.push((name.clone(), format!("({}) {}-{}", area_code, middle, end).into()));
             }
 
-            Parameter::Date {name, min, max} => {
-                let year = rng.random_range(min..=max);
-                let month = rng.random_range(1..=12);
-                let max_day = match month {
-                    2 => if year % 4 == 0 { 29 } else { 28 },
-                    4 | 6 | 9 | 11 => 30,
-                    _ => 31
-                };
-                let day = rng.random_range(1..=max_day);
-                
-                params.insert(name.clone(), format!("{:02}/{:02}/{:04}", month, day, year));
+            Parameter::Date { name, min, max, format } => {
+                params.push((name, datetime::random_date(rng, &min, &max, &format).into()));
+            }
+
+            Parameter::Time { name, min, max, format } => {
+                params.push((name, datetime::random_time(rng, min, max, &format).into()));
             }
 
-            Parameter::CheckBoxes { kvps } => {
+            Parameter::DateTime { name, min, max, format } => {
+                params.push((name, datetime::random_datetime(rng, &min, &max, &format).into()));
+            }
+
+            Parameter::CheckBoxes { kvps, probability } => {
                 for (name, value) in kvps {
-                    if rng.random_bool(0.5) {
-                        params.insert(name.clone(), value.clone());
+                    if rng.random_bool(probability) {
+                        params.push((name.clone(), value.clone().into()));
                     }
                 }
             }
 
-            Parameter::String { name, max_len } => {
+            Parameter::String { name, max_len, length } => {
                 let chars: Vec<char> = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789., "
                     .chars()
                     .collect();
-                let len = rng.random_range(1..=max_len);
+                let len = length.sample_len(rng, 1, max_len);
                 let random_string: String = (0..len)
                     .map(|_| chars[rng.random_range(0..chars.len())])
                     .collect();
-                params.insert(name.clone(), random_string);
+                params.push((name.clone(), random_string.into()));
             }
             
-            Parameter::Name{ name, max_len } => {
+            Parameter::Name { name, max_len, locale: Some(locale), length } => {
+                let max_len = length.sample_len(rng, 1, max_len);
+                params.push((name, locale.full_name(rng, max_len).into()));
+            }
+            
+            Parameter::Name { name, max_len, locale: None, length } => {
                 let chars: Vec<char> = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789"
                     .chars()
                     .collect();
-                let len = rng.random_range(1..=max_len);
+                let len = length.sample_len(rng, 1, max_len);
                 let first_name: String = (0..len)
                     .map(|_| chars[rng.random_range(0..chars.len())])
                     .collect();
@@ -131,67 +408,1221 @@ impl Parameter {
                     .map(|_| chars[rng.random_range(0..chars.len())])
                     .collect();
                 let full_name = format!("{first_name} {last_name}");
-                params.insert(name.clone(), full_name); 
+                params.push((name.clone(), full_name.into()));
             }
             
-            Parameter::OptionalString { name } => {
-                if rng.random_bool(0.5) {
-                    params.insert(name.clone(), String::new());
+            // generators that wrap other generators hand them the same rng and scope
+            Parameter::Choose { n, mut options } => {
+                options.shuffle(rng);
+                for option in options.iter().take(n) {
+                    params.extend(option.gen_params(rng, scope));
                 }
-            }
-            Parameter::ChooseAny {mut options } => {
-                options.shuffle(&mut rng);
-                if options.len() > 0 {
-                    let kvp = options[0].clone();
-                    params.insert(kvp.0.clone(), kvp.1.clone());
+            },
+            Parameter::Repeat { min, max, param } => {
+                for _ in 0..rng.random_range(min..=max) {
+                    params.extend(param.gen_params(rng, scope));
+                }
+            },
+            Parameter::Weighted { options } => {
+                // parsing makes sure the weights are valid
+                if let Ok(index) = WeightedIndex::new(options.iter().map(|(weight, _)| *weight)) {
+                    params.extend(options[index.sample(rng)].1.gen_params(rng, scope));
                 }
             },
-            Parameter::ChooseN { n, mut kvps } => {
-                kvps.shuffle(&mut rng);
-                for kvp in &kvps[0..n] {
-                    params.insert(kvp.0.clone(), kvp.1.clone());
+            Parameter::Maybe { probability, param } => {
+                if rng.random_bool(probability) {
+                    params.extend(param.gen_params(rng, scope));
                 }
             },
+            Parameter::Unique { param, seen } => {
+                params.extend(seen.draw(&param, rng, scope));
+            },
             Parameter::Static { name, val } => {
-                params.insert(name.clone(), val.clone());
+                params.push((name.clone(), scope.resolve(&val).into()));
+            },
+            // bound by gen_param_map, a `let` doesn't produce fields of its own
+            Parameter::Let { .. } => {},
+            Parameter::Csv { dataset, columns, order } => {
+                if let Some(row) = dataset.row(order, scope.index, rng) {
+                    for ((_, field), value) in columns.iter().zip(row) {
+                        params.push((field.clone(), value.clone().into()));
+                    }
+                }
+            },
+            Parameter::Template { name, parts } => {
+                let mut text = String::new();
+                for part in &parts {
+                    match part {
+                        TemplatePart::Value(value) => text.push_str(&scope.resolve(value)),
+                        TemplatePart::Generator(param) => text.push_str(&param.gen_text(rng, scope)),
+                    }
+                }
+                params.push((name, text.into()));
+            },
+            Parameter::Int { name, min, max, distribution } => {
+                params.push((name, distribution.sample_int(rng, min, max).to_string().into()));
+            },
+            Parameter::Float { name, min, max, precision, distribution } => {
+                let value = distribution.sample(rng, min, max);
+                params.push((name, distribution::format_decimal(value, precision, min, max).into()));
+            },
+            Parameter::Money { name, min, max, currency, distribution } => {
+                let value = distribution.sample(rng, min, max);
+                let decimals = distribution::currency_decimals(&currency);
+                params.push((name, distribution::format_decimal(value, decimals, min, max).into()));
+            },
+            Parameter::Regex { name, pattern } => {
+                params.push((name, pattern.generate(rng).into()));
+            },
+            Parameter::Counter { name, start, step, format } => {
+                // past `FormGenerator::limit`, where the numbers run out, the field is left out
+                if let Some(number) = ids::counter_value(start, step, scope.index) {
+                    params.push((name, format.format(number).into()));
+                }
+            },
+            Parameter::Uuid { name, version, fresh } => {
+                let value = match fresh {
+                    false => version.generate(rng),
+                    true => version.generate(&mut rand::rng()),
+                };
+                params.push((name, value.into()));
+            },
+            Parameter::PersonName { name, part, locale } => {
+                params.push((name, locale.name(rng, part).into()));
+            },
+            Parameter::Address { parts, locale } => {
+                let address = locale.address(rng);
+                for (part, field) in parts {
+                    params.push((field, address.part(part).to_string().into()));
+                }
+            },
+            Parameter::Phone { name, locale } => {
+                params.push((name, locale.phone(rng).into()));
+            },
+            Parameter::Checksum { name, kind, invalid } => {
+                params.push((name, kind.generate(rng, invalid).into()));
+            },
+            Parameter::Password { name, min, max, policy, length } => {
+                params.push((name, policy.generate(rng, min, max, &length).into()));
+            },
+            Parameter::Text { name, min, max, style, length } => {
+                params.push((name, style.generate(rng, min, max, &length).into()));
+            },
+            Parameter::Payload { name, set } => {
+                params.push((name, set.generate(rng, &scope.boundary).into()));
+            },
+            Parameter::File { name, source, content_type } => {
+                let (filename, data) = source.pick(rng);
+                let content_type = content_type
+                    .unwrap_or_else(|| upload::guess_content_type(&filename).to_string());
+                params.push((name, FieldValue::File { filename, content_type, data }));
             },
         }
         params
     }
     
-    fn try_parse(input: &str) -> Result<Self, std::io::Error> {
-        match parse_line(input) {
-            Ok(result) => {
-                return Ok(result.1);
+    /// first text value the generator produces, empty if it doesn't produce any
+    fn gen_text(&self, rng: &mut StdRng, scope: &Scope) -> String {
+        first_text(self.gen_params(rng, scope))
+    }
+    
+    /// reads and parses a config file and the files it includes. see the README for the grammar
+    pub fn get_params_from_config(path: &str) -> Result<Vec<Parameter>, ConfigError> {
+        loader::load(Path::new(path))
+    }
+    
+    /// reads the files a generator refers to (csv datasets, corpora, histograms and uploads)
+    /// relative to `base`, the directory of the config file the generator is written in, and pins
+    /// `now` relative dates to `now`, the moment the config was loaded. everything a run needs
+    /// from disk is read here, so a missing or malformed file fails before the first request
+    /// rather than halfway through a run. the data is kept behind an `Arc` that clones of the
+    /// generator share, and those types compare by path alone, since that's what the config says.
+    /// `syntax_error` turns a problem with the generator itself into an error pointing at it
+    fn resolve(&mut self, base: &Path, now: chrono::NaiveDateTime, syntax_error: &dyn Fn(String) -> ConfigError) -> Result<(), ConfigError> {
+        match self {
+            Parameter::File { source, .. } => source.resolve(base)?,
+            // a bound relative to `now` can only be compared to a fixed one once `now` is known
+            Parameter::Date { min, max, .. } => {
+                min.resolve(now);
+                max.resolve(now);
+                if min.at().date() > max.at().date() {
+                    return Err(syntax_error("max no smaller than min in `date(...)`".to_string()));
+                }
+            }
+            Parameter::DateTime { min, max, .. } => {
+                min.resolve(now);
+                max.resolve(now);
+                if min.at() > max.at() {
+                    return Err(syntax_error("max no smaller than min in `datetime(...)`".to_string()));
+                }
+            }
+            Parameter::Let { param, .. }
+            | Parameter::Repeat { param, .. }
+            | Parameter::Maybe { param, .. }
+            | Parameter::Unique { param, .. } => param.resolve(base, now, syntax_error)?,
+            Parameter::Choose { options, .. } => {
+                for option in options {
+                    option.resolve(base, now, syntax_error)?;
+                }
+            }
+            Parameter::Weighted { options } => {
+                for (_, option) in options {
+                    option.resolve(base, now, syntax_error)?;
+                }
+            }
+            Parameter::Csv { dataset, columns, .. } => {
+                let columns: Vec<&str> = columns.iter().map(|(column, _)| column.as_str()).collect();
+                dataset.load(base, &columns)?;
+            }
+            Parameter::Text { style, length, .. } => {
+                if let TextStyle::Markov(corpus) = style {
+                    corpus.load(base)?;
+                }
+                length.load(base)?;
+            }
+            Parameter::Int { distribution, .. }
+            | Parameter::Float { distribution, .. }
+            | Parameter::Money { distribution, .. }
+            | Parameter::Email { length: distribution, .. }
+            | Parameter::String { length: distribution, .. }
+            | Parameter::Name { length: distribution, .. }
+            | Parameter::Password { length: distribution, .. } => distribution.load(base)?,
+            Parameter::Template { parts, .. } => {
+                for part in parts {
+                    if let TemplatePart::Generator(param) = part {
+                        param.resolve(base, now, syntax_error)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+    
+    /// generates form number `index`, with fields in config order. all randomness comes from `rng`.
+    /// boundary payloads make up a boundary of their own, as the real one isn't known here
+    pub fn gen_param_map(param_list: &[Parameter], index: u64, rng: &mut StdRng) -> FormFields {
+        Self::gen_form(param_list, index, "", rng, None).0
+    }
+    
+    /// like `gen_param_map` for a form sent with `boundary`, with the generator at position
+    /// `invalid` producing an invalid value, which is given along with the fields and whether a
+    /// `unique(...)` ran out of values. a broken `let` gives the violation of its generator, it's up
+    /// to the caller to name the field that copies it
+    fn gen_form(param_list: &[Parameter], index: u64, boundary: &str, rng: &mut StdRng, invalid: Option<usize>) -> (FormFields, Option<Violation>, bool) {
+        let mut scope = Scope { index, boundary: boundary.to_string(), ..Scope::default() };
+        let mut violation = None;
+        for (i, param) in param_list.iter().enumerate() {
+            if let Parameter::Let { var, param } = param {
+                // a let binds the first text value its generator produces
+                let value = match invalid == Some(i) {
+                    true => {
+                        let (fields, broke) = param.gen_invalid(rng, &scope);
+                        violation = broke;
+                        first_text(fields)
+                    }
+                    false => param.gen_text(rng, &scope),
+                };
+                scope.vars.insert(var.clone(), value);
+            } else if invalid == Some(i) {
+                let (fields, broke) = param.gen_invalid(rng, &scope);
+                violation = broke;
+                scope.fields.extend(fields);
+            } else {
+                let fields = param.gen_params(rng, &scope);
+                scope.fields.extend(fields);
+            }
+        }
+        (scope.fields, violation, scope.exhausted.get())
+    }
+}
+
+/// first text value out of `fields`, empty if there is none
+fn first_text(fields: FormFields) -> String {
+    fields.into_iter().find_map(|(_, value)| match value {
+        FieldValue::Text(text) => Some(text),
+        FieldValue::File { .. } => None,
+    }).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{collections::HashSet, fs};
+    
+    impl Parameter {
+        fn get_params_from_config_str(config: &str) -> Vec<Parameter> {
+            parse_config(config).unwrap()
+        }
+    }
+    
+    fn field(name: &str, val: &str) -> (String, FieldValue) {
+        (name.to_string(), FieldValue::Text(val.to_string()))
+    }
+    
+    fn kvp(name: &str, val: &str) -> (String, String) {
+        (name.to_string(), val.to_string())
+    }
+    
+    /// all text values of the fields called `name`, in form order
+    fn texts(fields: &[(String, FieldValue)], name: &str) -> Vec<String> {
+        fields.iter().filter(|(field, _)| field == name).map(|(_, value)| match value {
+            FieldValue::Text(text) => text.clone(),
+            other => panic!("{name}: unexpected value {other:?}"),
+        }).collect()
+    }
+    
+    /// the text value of the first field called `name`
+    fn text(fields: &[(String, FieldValue)], name: &str) -> String {
+        texts(fields, name).into_iter().next().unwrap_or_else(|| panic!("no field {name} in {fields:?}"))
+    }
+    
+    /// a directory for the config files of one test, removed when the test ends even if it fails
+    struct TempConfig {
+        dir: PathBuf,
+    }
+    
+    impl TempConfig {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("form_loadtester_{name}_{}", std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempConfig { dir }
+        }
+        
+        fn path(&self, file: &str) -> PathBuf {
+            self.dir.join(file)
+        }
+        
+        fn write(&self, file: &str, contents: &str) {
+            let path = self.path(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        
+        fn load_file(&self, file: &str) -> Result<Vec<Parameter>, ConfigError> {
+            Parameter::get_params_from_config(self.path(file).to_str().unwrap())
+        }
+        
+        /// writes `body` to form.conf and loads it
+        fn load(&self, body: &str) -> Result<Vec<Parameter>, ConfigError> {
+            self.write("form.conf", body);
+            self.load_file("form.conf")
+        }
+    }
+    
+    impl Drop for TempConfig {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+    
+    #[test]
+    fn test_gen_param_map_keeps_order_and_duplicates() {
+        let params = vec![
+            Parameter::Static { name: "b".to_string(), val: "1".into() },
+            Parameter::Static { name: "a".to_string(), val: "2".into() },
+            Parameter::Choose { n: 2, options: vec![
+                Parameter::Static { name: "box".to_string(), val: "x".into() },
+                Parameter::Static { name: "box".to_string(), val: "y".into() },
+            ] },
+            Parameter::Static { name: "b".to_string(), val: "3".into() },
+        ];
+        let fields = Parameter::gen_param_map(&params, 0, &mut form_rng(0, 0));
+        assert_eq!(fields.len(), 5);
+        assert_eq!(fields[..2], [field("b", "1"), field("a", "2")]);
+        let mut boxes = fields[2..4].to_vec();
+        boxes.sort_by(|a, b| format!("{a:?}").cmp(&format!("{b:?}")));
+        assert_eq!(boxes, [field("box", "x"), field("box", "y")]);
+        assert_eq!(fields[4], field("b", "3"));
+    }
+    
+    #[test]
+    fn test_seeded_forms_are_reproducible() {
+        let params = vec![
+            Parameter::Email { name: "email".to_string(), domains: vec!["a.com".to_string(), "b.com".to_string()], length: Distribution::Uniform },
+            Parameter::String { name: "s".to_string(), max_len: 50, length: Distribution::Uniform },
+            Parameter::Date {
+                name: "d".to_string(),
+                min: DateTimeBound::At(chrono::NaiveDate::from_ymd_opt(1900, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap()),
+                max: DateTimeBound::At(chrono::NaiveDate::from_ymd_opt(2100, 12, 31).unwrap().and_hms_opt(0, 0, 0).unwrap()),
+                format: "%m/%d/%Y".to_string()
             },
-            Err(_) => Err(std::io::Error::new(std::io::ErrorKind::Other, "Parsing error")),
+            Parameter::CheckBoxes { kvps: vec![kvp("box", "x"), kvp("box", "y"), kvp("box", "z")], probability: 0.5 },
+        ];
+        assert_eq!(
+            Parameter::gen_param_map(&params, 7, &mut form_rng(42, 7)),
+            Parameter::gen_param_map(&params, 7, &mut form_rng(42, 7))
+        );
+        assert_ne!(
+            Parameter::gen_param_map(&params, 7, &mut form_rng(42, 7)),
+            Parameter::gen_param_map(&params, 8, &mut form_rng(42, 8))
+        );
+    }
+    
+    #[test]
+    fn test_multipart_body() {
+        let form = Form {
+            boundary: "XyZ".to_string(),
+            fields: vec![field("a", "1"), field("quote\"d", "two\r\nlines"), field("a", "")],
+            violation: None,
+        };
+        assert_eq!(form.content_type(), "multipart/form-data; boundary=XyZ");
+        assert_eq!(
+            String::from_utf8(form.body()).unwrap(),
+            "--XyZ\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n1\r\n\
+             --XyZ\r\nContent-Disposition: form-data; name=\"quote%22d\"\r\n\r\ntwo\r\nlines\r\n\
+             --XyZ\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n\r\n\
+             --XyZ--\r\n"
+        );
+    }
+    
+    #[test]
+    fn test_form_generator_is_deterministic() {
+        let params = vec![Parameter::String { name: "s".to_string(), max_len: 20, length: Distribution::Uniform }];
+        let generator = FormGenerator::new(params, 99, true);
+        assert_eq!(generator.generate(3).body(), generator.generate(3).body());
+    }
+    
+    #[test]
+    fn test_random_file_part() {
+        let params = vec![Parameter::File {
+            name: "blob".to_string(),
+            source: FileSource::Random { min_size: 3, max_size: 5, distribution: Distribution::Uniform },
+            content_type: None
+        }];
+        match &Parameter::gen_param_map(&params, 1, &mut form_rng(1, 1))[..] {
+            [(name, FieldValue::File { filename, content_type, data })] => {
+                assert_eq!((name.as_str(), filename.as_str(), content_type.as_str()), ("blob", "random.bin", "application/octet-stream"));
+                assert!((3..=5).contains(&data.len()));
+            }
+            other => panic!("unexpected fields {other:?}"),
         }
+    }
+    
+    #[test]
+    fn test_uploads_are_read_when_the_config_is_loaded() {
+        let dir = TempConfig::new("uploads");
+        fs::create_dir_all(dir.path("empty")).unwrap();
+        dir.write("cat.png", "png");
+        dir.write("docs/a.txt", "a");
+        dir.write("docs/b.pdf", "b");
+        let params = dir.load(r#"
+            file("avatar",path("cat.png"))
+            file("doc",dir("docs"))
+        "#).unwrap();
+        // sending doesn't read the files again, so changing them during a run changes nothing
+        fs::remove_file(dir.path("cat.png")).unwrap();
+        dir.write("docs/a.txt", "changed");
+        let mut docs = HashSet::new();
+        for index in 0..20 {
+            let fields = Parameter::gen_param_map(&params, index, &mut form_rng(4, index));
+            let [(_, FieldValue::File { filename, data, .. }), (_, FieldValue::File { filename: doc, data: doc_data, content_type })] = &fields[..] else { panic!("{fields:?}") };
+            assert_eq!((filename.as_str(), data.as_slice()), ("cat.png", b"png".as_slice()));
+            match doc.as_str() {
+                "a.txt" => assert_eq!((doc_data.as_slice(), content_type.as_str()), (b"a".as_slice(), "text/plain")),
+                "b.pdf" => assert_eq!((doc_data.as_slice(), content_type.as_str()), (b"b".as_slice(), "application/pdf")),
+                other => panic!("unexpected upload {other}"),
+            }
+            docs.insert(doc.clone());
+        }
+        assert_eq!(docs.len(), 2);
         
+        assert!(dir.load(r#"file("doc",dir("empty"))"#).is_err());
     }
     
-    pub fn get_params_from_config(path: &str) -> Result<Vec<Parameter>, std::io::Error> {
-        let contents: String = fs::read_to_string(path)?;
+    #[test]
+    fn test_regex_output_matches_pattern() {
+        let pattern = Pattern::parse(r"^[A-Z]{2}-\d{4}(-[a-z]+)?$").unwrap();
+        let mut rng = form_rng(5, 0);
+        for _ in 0..100 {
+            let value = pattern.generate(&mut rng);
+            let (prefix, rest) = value.split_at(3);
+            assert!(prefix.chars().take(2).all(|c| c.is_ascii_uppercase()) && prefix.ends_with('-'), "{value}");
+            assert!(rest[..4].chars().all(|c| c.is_ascii_digit()), "{value}");
+            assert!(rest[4..].is_empty() || (rest[4..].starts_with('-') && rest[5..].chars().all(|c| c.is_ascii_lowercase())), "{value}");
+        }
         
-        let mut param_list = Vec::new();
-        for line in contents.lines().filter_map(|item| {
-            let item = item.trim();
-            if item.is_empty() {
-                None
-            } else {
-                Some(item)
+        // bytes past ascii would be sent as two byte characters the pattern doesn't match
+        for source in [r"(?-u:[\x80-\xff])", r"(?-u:\xff)", r"(?-u:[\x00-\xff])"] {
+            assert_eq!(Pattern::parse(source).unwrap_err(), "pattern can match invalid UTF-8", "{source}");
+        }
+        let pattern = Pattern::parse(r"(?-u:[\x00-\x7f]{20})").unwrap();
+        let value = pattern.generate(&mut rng);
+        assert!(value.len() == 20 && value.is_ascii(), "{value:?}");
+    }
+    
+    #[test]
+    fn test_numbers_stay_in_range() {
+        let distributions = [
+            Distribution::Uniform,
+            Distribution::Normal { mean: 90.0, sd: 30.0 },
+            Distribution::LogUniform,
+            Distribution::Exponential { mean: 5.0 },
+        ];
+        let mut rng = form_rng(3, 0);
+        for distribution in distributions {
+            let params = vec![
+                Parameter::Int { name: "i".to_string(), min: -10, max: 100, distribution: distribution.clone() },
+                Parameter::Float { name: "f".to_string(), min: 0.1, max: 99.9, precision: 1, distribution: distribution.clone() },
+                Parameter::Money { name: "m".to_string(), min: 0.0, max: 5000.0, currency: "JPY".to_string(), distribution },
+            ];
+            for _ in 0..200 {
+                let fields = Parameter::gen_param_map(&params, 0, &mut rng);
+                let values: Vec<f64> = fields.iter().map(|(_, value)| match value {
+                    FieldValue::Text(text) => text.parse().unwrap(),
+                    other => panic!("unexpected value {other:?}"),
+                }).collect();
+                assert!((-10.0..=100.0).contains(&values[0]), "{fields:?}");
+                assert!((0.1..=99.9).contains(&values[1]), "{fields:?}");
+                assert!((0.0..=5000.0).contains(&values[2]), "{fields:?}");
+                assert!(!format!("{:?}", fields[2].1).contains('.'), "{fields:?}");
+            }
+        }
+        for value in [0.0, 0.114, 0.116, 0.2, 1.0] {
+            assert_eq!(distribution::format_decimal(value, 2, 0.11, 0.11), "0.11");
+            let text = distribution::format_decimal(value, 1, 0.11, 0.12);
+            assert!((0.11..=0.12).contains(&text.parse::<f64>().unwrap()), "{text}");
+        }
+    }
+    
+    #[test]
+    fn test_dates_respect_calendar_and_bounds() {
+        let day = |y, m, d| DateTimeBound::At(chrono::NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(0, 0, 0).unwrap());
+        let params = vec![
+            // 1900 isn't a leap year
+            Parameter::Date { name: "d".to_string(), min: day(1900, 2, 27), max: day(1900, 3, 1), format: "%Y-%m-%d".to_string() },
+            Parameter::Time {
+                name: "t".to_string(),
+                min: chrono::NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+                max: chrono::NaiveTime::from_hms_opt(1, 0, 0).unwrap(),
+                format: "%H".to_string()
+            },
+        ];
+        let mut rng = form_rng(8, 0);
+        for _ in 0..200 {
+            let fields = Parameter::gen_param_map(&params, 0, &mut rng);
+            let FieldValue::Text(date) = &fields[0].1 else { panic!() };
+            assert!(["1900-02-27", "1900-02-28", "1900-03-01"].contains(&date.as_str()), "{date}");
+            let FieldValue::Text(hour) = &fields[1].1 else { panic!() };
+            assert!(["23", "00", "01"].contains(&hour.as_str()), "{hour}");
+        }
+        
+        // a fixed bound and one relative to now are compared when the config is loaded
+        let dir = TempConfig::new("dates");
+        for (body, func) in [("date(\"d\",2999,now)", "date"), ("datetime(\"t\",\"2999-01-01T00:00:00\",now-1d)", "datetime")] {
+            let err = dir.load(&format!("yes_no(\"a\")\n{body}")).unwrap_err();
+            let ConfigError::Syntax { line, expected, .. } = err else { panic!("unexpected error {err}") };
+            assert_eq!((line, expected), (2, format!("max no smaller than min in `{func}(...)`")));
+        }
+        assert!(dir.load("date(\"d\",now-30d,2999)\ndatetime(\"t\",now,now)").is_ok());
+    }
+    
+    #[test]
+    fn test_refs_copy_values_within_a_form() {
+        let params = Parameter::get_params_from_config_str(r#"
+            let pw = string("unused", 16)
+            name("full_name", 8)
+            email("email", ["x.com"])
+            static("confirm_email", ref("email", upper))
+            static("username", ref("full_name", slug))
+            static("password", ref("pw"))
+            static("password_confirm", ref("pw"))
+        "#);
+        for index in 0..20 {
+            let fields = Parameter::gen_param_map(&params, index, &mut form_rng(11, index));
+            let text = |name: &str| text(&fields, name);
+            assert_eq!(fields.len(), 6);
+            assert_eq!(text("confirm_email"), text("email").to_uppercase());
+            assert_eq!(text("username"), text("full_name").to_lowercase().replace(' ', "_"));
+            assert_eq!(text("password"), text("password_confirm"));
+            assert!(!text("password").is_empty());
+        }
+    }
+    
+    #[test]
+    fn test_template_mixes_text_generators_and_refs() {
+        let params = Parameter::get_params_from_config_str(r#"
+            let customer = regex("c", "[A-Z][a-z]{3}")
+            template("subject", "Order #{int(1, 999)} from {ref:customer} {{{regex("[0-9]{2}")}}}")
+        "#);
+        for index in 0..20 {
+            let fields = Parameter::gen_param_map(&params, index, &mut form_rng(5, index));
+            let [(name, FieldValue::Text(subject))] = fields.as_slice() else { panic!("unexpected fields {fields:?}") };
+            assert_eq!(name, "subject");
+            let rest = subject.strip_prefix("Order #").unwrap();
+            let (number, rest) = rest.split_once(" from ").unwrap();
+            assert!((1..=999).contains(&number.parse::<i64>().unwrap()));
+            let (customer, digits) = rest.split_once(" {").unwrap();
+            assert_eq!(customer.len(), 4);
+            assert!(customer.starts_with(|c: char| c.is_ascii_uppercase()));
+            assert_eq!(digits.len(), 3);
+            assert!(digits.ends_with('}') && digits[..2].chars().all(|c| c.is_ascii_digit()));
+        }
+    }
+    
+    #[test]
+    fn test_csv_rows_keep_fields_together() {
+        let dir = TempConfig::new("csv");
+        dir.write("accounts.csv", "name,email,phone\nAda,ada@x.com,1\nBob,bob@x.com,2\nCy,cy@x.com,3\n");
+        let text = |form: &Form, name: &str| text(&form.fields, name);
+        
+        let generator = FormGenerator::new(dir.load(r#"csv("accounts.csv", [("email","user"),("name","full_name")])"#).unwrap(), 1, false);
+        assert_eq!(generator.limit(), None);
+        let names: Vec<String> = (0..4).map(|index| text(&generator.generate(index), "full_name")).collect();
+        assert_eq!(names, ["Ada", "Bob", "Cy", "Ada"]);
+        assert_eq!(text(&generator.generate(1), "user"), "bob@x.com");
+        
+        let generator = FormGenerator::new(dir.load(r#"csv("accounts.csv", [("name","n"),("email","e")], unique)"#).unwrap(), 1, false);
+        assert_eq!(generator.limit(), Some(3));
+        let mut names: Vec<String> = (0..3).map(|index| {
+            let form = generator.generate(index);
+            assert_eq!(text(&form, "e"), format!("{}@x.com", text(&form, "n").to_lowercase()));
+            text(&form, "n")
+        }).collect();
+        names.sort();
+        assert_eq!(names, ["Ada", "Bob", "Cy"]);
+        assert!(generator.generate(3).fields.is_empty());
+        
+        let err = dir.load(r#"csv("accounts.csv", [("mail","e")])"#).unwrap_err();
+        let ConfigError::Syntax { line, expected, .. } = err else { panic!("unexpected error {err}") };
+        assert_eq!(line, 1);
+        assert_eq!(expected, "a file that can be loaded, `accounts.csv` can't be (no column named `mail`)");
+    }
+    
+    #[test]
+    fn test_includes_are_relative_and_cycles_are_caught() {
+        let dir = TempConfig::new("include");
+        dir.write("common/logo.png", "png");
+        dir.write("common/contact.form", r#"
+            include("fields.form")
+            fragment contact {
+                email("email", ["x.com"])
+                file("logo", path("logo.png"))
+            }
+        "#);
+        dir.write("common/fields.form", r#"yes_no("newsletter")"#);
+        let params = dir.load(r#"
+            include("common/contact.form")
+            use contact("billing_")
+            static("again", ref("newsletter"))
+        "#).unwrap();
+        let names: Vec<&str> = params.iter().flat_map(Parameter::field_names).collect();
+        assert_eq!(names, ["newsletter", "billing_email", "billing_logo", "again"]);
+        assert_eq!(params[2], Parameter::File {
+            name: "billing_logo".to_string(),
+            source: FileSource::Path(Uploads::new(dir.path("common/logo.png"))),
+            content_type: None
+        });
+        
+        // a file of fragments included by two others is only loaded once
+        dir.write("common/address.form", r#"fragment address { regex("street", "[a-z]+") }"#);
+        dir.write("billing.form", r#"
+            include("common/address.form")
+            use address("billing_")
+        "#);
+        dir.write("shipping.form", r#"
+            include("./common/address.form")
+            use address("shipping_")
+        "#);
+        dir.write("both.conf", r#"
+            include("billing.form")
+            include("shipping.form")
+        "#);
+        let params = dir.load_file("both.conf").unwrap();
+        let names: Vec<&str> = params.iter().flat_map(Parameter::field_names).collect();
+        assert_eq!(names, ["billing_street", "shipping_street"]);
+        
+        // a file that sends fields would send them twice
+        dir.write("shipping.form", "\n\ninclude(\"common/contact.form\")");
+        dir.write("both.conf", "include(\"common/contact.form\")\ninclude(\"shipping.form\")");
+        let err = dir.load_file("both.conf").unwrap_err();
+        let ConfigError::Syntax { path, line, expected, .. } = err else { panic!("unexpected error {err}") };
+        assert_eq!((path, line), (dir.path("shipping.form"), 3));
+        assert_eq!(expected, "an include of a file that isn't included yet, `common/contact.form` sends fields and is already included at both.conf:1");
+        
+        dir.write("missing.conf", "yes_no(\"a\")\ninclude(\"nope.form\")");
+        let err = dir.load_file("missing.conf").unwrap_err();
+        let ConfigError::Syntax { path, line, expected, .. } = err else { panic!("unexpected error {err}") };
+        assert_eq!((path, line), (dir.path("missing.conf"), 2));
+        assert!(expected.starts_with("an include of a file that can be read, `nope.form` can't be"), "{expected}");
+        
+        dir.write("secret.form", "\nlet pw = string(\"pw\",8)");
+        dir.write("let.conf", "include(\"secret.form\")\nlet pw = string(\"pw\",12)");
+        let err = dir.load_file("let.conf").unwrap_err();
+        let ConfigError::Syntax { path, line, expected, .. } = err else { panic!("unexpected error {err}") };
+        assert_eq!((path, line), (dir.path("let.conf"), 2));
+        assert_eq!(expected, "a `let` name that isn't taken yet, `pw` is already defined at secret.form:2");
+        
+        dir.write("common/fields.form", r#"include("contact.form")"#);
+        let err = dir.load_file("form.conf").unwrap_err();
+        let ConfigError::Syntax { path, line, expected, .. } = err else { panic!("unexpected error {err}") };
+        assert_eq!((path, line), (dir.path("common/fields.form"), 1));
+        assert!(expected.ends_with("(common/contact.form -> common/fields.form -> common/contact.form)"), "{expected}");
+    }
+    
+    #[test]
+    fn test_nested_generators() {
+        let params = Parameter::get_params_from_config_str(r#"
+            one_of(static("contact", "mail"), static("contact", "phone"))
+            repeat(2, 4, regex("tag", "[a-z]{3}"))
+            maybe(0.0, yes_no("never"))
+            maybe(1.0, repeat(1, 1, static("always", "x")))
+            template("t", "{one_of(int("", 1, 1), int("", 2, 2))}")
+        "#);
+        for index in 0..20 {
+            let fields = Parameter::gen_param_map(&params, index, &mut form_rng(3, index));
+            let values = |name: &str| texts(&fields, name);
+            assert!(matches!(values("contact")[..], [ref contact] if contact == "mail" || contact == "phone"));
+            let tags = values("tag");
+            assert!((2..=4).contains(&tags.len()) && tags.iter().all(|tag| tag.len() == 3));
+            assert!(values("never").is_empty());
+            assert_eq!(values("always"), ["x"]);
+            assert!(matches!(values("t")[0].as_str(), "1" | "2"));
+        }
+    }
+    
+    #[test]
+    fn test_weights_and_probabilities_shape_the_output() {
+        let params = Parameter::get_params_from_config_str(r#"
+            weighted([("plan","free",70), ("plan","pro",30), ("plan","never",0)])
+            yes_no("agree", 1)
+            checkboxes([("box","a"), ("box","b")], 0)
+            string("note", 5)? 0
+            cellphone("phone")? 1
+        "#);
+        let mut free = 0;
+        for index in 0..1000 {
+            let fields = Parameter::gen_param_map(&params, index, &mut form_rng(9, index));
+            assert_eq!(fields.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), ["plan", "agree", "phone"]);
+            assert_eq!(fields[1], field("agree", "Yes"));
+            match &fields[0].1 {
+                FieldValue::Text(plan) if plan == "free" => free += 1,
+                FieldValue::Text(plan) => assert_eq!(plan, "pro"),
+                other => panic!("unexpected value {other:?}"),
+            }
+        }
+        assert!((620..780).contains(&free), "{free} of 1000 forms got the free plan");
+    }
+    
+    #[test]
+    fn test_invalid_forms_break_one_constraint() {
+        let params = Parameter::get_params_from_config_str(r#"
+            string("note",10)
+            date("dob",1950,2005,"%d.%m.%Y")
+            int("qty",1,100)
+            static("kind","order")
+        "#);
+        let generator = FormGenerator::new(params, 5, false).with_invalid_values(true);
+        let mut kinds = std::collections::BTreeSet::new();
+        for index in 0..200 {
+            let form = generator.generate(index);
+            let violation = form.violation.clone().expect("every form has a violation");
+            let text = |name: &str| text(&form.fields, name);
+            let count = |name: &str| form.fields.iter().filter(|(field, _)| *field == name).count();
+            match violation.kind {
+                ViolationKind::OverLength => assert!(text("note").len() > 10, "{form:?}"),
+                ViolationKind::ImpossibleDate => assert!(text("dob").starts_with("30.02."), "{form:?}"),
+                ViolationKind::OutOfRange => {
+                    let qty: i64 = text("qty").parse().unwrap();
+                    assert!(!(1..=100).contains(&qty), "{form:?}");
+                }
+                ViolationKind::MissingField => assert_eq!(count(&violation.field), 0, "{form:?}"),
+                ViolationKind::DuplicatedField => assert_eq!(count(&violation.field), 2, "{form:?}"),
+                other => panic!("unexpected violation {other:?}"),
+            }
+            kinds.insert(violation.kind);
+        }
+        assert_eq!(kinds.len(), 5);
+        assert_eq!(generator.generate(3), generator.generate(3));
+        
+        // numbers out of a huge range are still finite and really outside it
+        let params = Parameter::get_params_from_config_str(r#"
+            float("huge",-1e300,1e300,15)
+            float("wide",0,100000000000000000000,2)
+            money("top",1,1.7976931348623157e308,"USD")
+        "#);
+        for param in &params {
+            let (Parameter::Float { min, max, .. } | Parameter::Money { min, max, .. }) = *param else { panic!() };
+            let mut rng = form_rng(5, 0);
+            for _ in 0..50 {
+                let (fields, violation) = param.gen_invalid(&mut rng, &Scope::default());
+                assert_eq!(violation.map(|violation| violation.kind), Some(ViolationKind::OutOfRange));
+                let value: f64 = text(&fields, &fields[0].0).parse().unwrap();
+                assert!(value.is_finite() && !(min..=max).contains(&value), "{fields:?}");
+            }
+        }
+        let all = Parameter::Float { name: "f".to_string(), min: f64::MIN, max: f64::MAX, precision: 0, distribution: Distribution::Uniform };
+        assert_eq!(all.violation(), None);
+        
+        // a month and year, or an hour on its own, are always valid whatever is written for them
+        let params = Parameter::get_params_from_config_str(r#"
+            date("d",1950,2005,"%m/%Y")
+            time("t","%H h")
+            date("full",1950,2005,"%D")
+            time("clock","%I:%M %p")
+        "#);
+        let kinds: Vec<_> = params.iter().map(Parameter::violation).collect();
+        assert_eq!(kinds, [None, None, Some(ViolationKind::ImpossibleDate), Some(ViolationKind::ImpossibleTime)]);
+        let generator = FormGenerator::new(params[..2].to_vec(), 5, false).with_invalid_values(true);
+        for index in 0..50 {
+            let violation = generator.generate(index).violation.expect("every form has a violation");
+            assert!(matches!(violation.kind, ViolationKind::MissingField | ViolationKind::DuplicatedField), "{violation}");
+        }
+    }
+    
+    #[test]
+    fn test_invalid_values_reach_wrapped_generators() {
+        let params = Parameter::get_params_from_config_str(r#"
+            string("note",10)?0.0
+            maybe(0.0, email("email",["x.com"]))
+            one_of(static("plan","free"), int("seats",1,10))
+            repeat(1,3,int("qty",1,100))
+            let pw = string("pw",8)
+            static("pw_copy",ref("pw"))
+        "#);
+        let generator = FormGenerator::new(params, 8, false).with_invalid_values(true);
+        let mut broken = HashSet::new();
+        for index in 0..400 {
+            let form = generator.generate(index);
+            let violation = form.violation.clone().expect("every form has a violation");
+            let values = texts(&form.fields, &violation.field);
+            let number = |text: &str| text.parse::<i64>().unwrap();
+            match (violation.kind, violation.field.as_str()) {
+                (ViolationKind::OverLength, "note") => assert!(values[0].len() > 10, "{form:?}"),
+                (ViolationKind::OverLength, "pw_copy") => assert!(values[0].len() > 8, "{form:?}"),
+                (ViolationKind::MalformedEmail, "email") => assert_eq!(values.len(), 1, "{form:?}"),
+                (ViolationKind::OutOfRange, "seats") => assert!(!(1..=10).contains(&number(&values[0])), "{form:?}"),
+                (ViolationKind::OutOfRange, "qty") => {
+                    assert!(values.iter().any(|qty| !(1..=100).contains(&number(qty))), "{form:?}");
+                }
+                (ViolationKind::MissingField | ViolationKind::DuplicatedField, _) => continue,
+                other => panic!("unexpected violation {other:?}"),
+            }
+            broken.insert(violation.field);
+        }
+        assert_eq!(broken.len(), 5, "{broken:?}");
+        
+        // a choice of none of its options is broken by sending a broken option on its own
+        let choose = Parameter::Choose { n: 0, options: Parameter::get_params_from_config_str(r#"
+            static("plan","free")
+            int("seats",1,10)
+        "#) };
+        let mut rng = form_rng(8, 0);
+        for _ in 0..20 {
+            let (fields, violation) = choose.gen_invalid(&mut rng, &Scope::default());
+            assert_eq!(violation.map(|violation| violation.kind), Some(ViolationKind::OutOfRange));
+            assert_eq!(fields.len(), 1, "{fields:?}");
+            assert!(!(1..=10).contains(&text(&fields, "seats").parse::<i64>().unwrap()), "{fields:?}");
+        }
+        
+        // a let no field copies is never sent, so breaking it wouldn't make the form invalid
+        let params = Parameter::get_params_from_config_str(r#"
+            let pw = string("pw",8)
+            static("x","1")
+        "#);
+        let generator = FormGenerator::new(params, 8, false).with_invalid_values(true);
+        for index in 0..50 {
+            let violation = generator.generate(index).violation.expect("every form has a violation");
+            assert!(matches!(violation.kind, ViolationKind::MissingField | ViolationKind::DuplicatedField), "{violation}");
+            assert_eq!(violation.field, "x");
+        }
+    }
+    
+    #[test]
+    fn test_only_required_fields_go_missing_or_twice() {
+        let params = Parameter::get_params_from_config_str(r#"
+            checkboxes([("c","a"),("c","b")])
+            optional("o")
+            yes_no("maybe")? 0.5
+            one_of(static("p","1"), static("p","2"))
+            yes_no("agree")
+        "#);
+        let generator = FormGenerator::new(params, 6, false).with_invalid_values(true);
+        for index in 0..100 {
+            let violation = generator.generate(index).violation.expect("every form has a violation");
+            assert_eq!(violation.field, "agree", "{violation}");
+        }
+        
+        // leaving out or repeating a checkbox is valid, so there's nothing to break
+        let params = Parameter::get_params_from_config_str(r#"checkboxes([("c","a"),("c","b")])"#);
+        let generator = FormGenerator::new(params, 6, false).with_invalid_values(true);
+        assert!((0..20).all(|index| generator.generate(index).violation.is_none()));
+    }
+    
+    #[test]
+    fn test_payloads_come_from_their_set() {
+        let params = Parameter::get_params_from_config_str(r#"
+            payload("u",unicode)
+            payload("c",crlf)
+            payload("b",boundary)
+            payload("p",percent)
+            payload("l",long)
+        "#);
+        let generator = FormGenerator::new(params, 2, false);
+        let mut with_boundary = 0;
+        for index in 0..50 {
+            let form = generator.generate(index);
+            let text: Vec<&str> = form.fields.iter().map(|(_, value)| match value {
+                FieldValue::Text(text) => text.as_str(),
+                other => panic!("unexpected value {other:?}"),
+            }).collect();
+            assert!(text[0].chars().any(|c| !c.is_ascii() || c.is_ascii_control()), "{:?}", text[0]);
+            assert!(text[1].contains(['\r', '\n']), "{:?}", text[1]);
+            assert!(text[2].contains("--") || text[2] == form.boundary, "{:?}", text[2]);
+            with_boundary += text[2].contains(&form.boundary) as u32;
+            assert!(text[3].contains(['%', '+']), "{:?}", text[3]);
+            assert!(text[4].len() >= 1024 && !text[4].contains(char::is_whitespace));
+        }
+        assert!(with_boundary > 15, "{with_boundary} of 50 boundary payloads had the form's boundary");
+        assert!(parse_config("payload(\"p\",sql)").is_err());
+    }
+    
+    #[test]
+    fn test_unique_values_never_repeat() {
+        let params = Parameter::get_params_from_config_str(r#"
+            fragment pick { unique(int("n",1,40)) }
+            use pick("a_")
+            use pick("b_")
+        "#);
+        let generator = FormGenerator::new(params, 4, false);
+        let mut seen = [HashSet::new(), HashSet::new()];
+        for index in 0..40 {
+            let form = generator.try_generate(index).expect("40 values to go around");
+            for (seen, (_, value)) in seen.iter_mut().zip(form.fields) {
+                assert!(seen.insert(format!("{value:?}")), "value repeated in form {index}");
             }
-        }) {
-            let param = Parameter::try_parse(line)?;
-            param_list.push(param);
         }
-        Ok(param_list)
+        assert_eq!(generator.exhausted(), None);
+        assert_eq!(generator.try_generate(40), None);
+        assert_eq!(generator.exhausted().as_deref(), Some("a_n"));
+        
+        // only a form whose own draw came up empty is given up, not every form after the first
+        let params = Parameter::get_params_from_config_str(r#"one_of(unique(yes_no("y")),static("s","x"))"#);
+        let generator = FormGenerator::new(params, 4, false);
+        let forms: Vec<Option<Form>> = (0..40).map(|index| generator.try_generate(index)).collect();
+        assert!(generator.exhausted().is_some());
+        assert_eq!(forms.iter().flatten().filter(|form| form.fields[0].0 == "y").count(), 2);
+        assert!(forms.iter().flatten().filter(|form| form.fields[0].0 == "s").count() > 10);
+        assert!(forms.iter().skip_while(|form| form.is_some()).any(Option::is_some));
+        
+        let generator = FormGenerator::new(Parameter::get_params_from_config_str("unique(yes_no(\"y\"))"), 4, false);
+        let mut preview = Vec::new();
+        preview::write_forms(&mut preview, &generator, 0, 10, PreviewFormat::Csv).unwrap();
+        assert_eq!(String::from_utf8(preview).unwrap().lines().count(), 3);
     }
     
-    pub fn gen_param_map(param_list: &Vec<Parameter>) -> HashMap<String,String> {
-        let mut params = Vec::new();
-        for p in param_list {
-            params.push(p.gen_params());
+    #[test]
+    fn test_counters_and_ids_are_unique_across_threads() {
+        let params = Parameter::get_params_from_config_str(r#"
+            counter("order_id",1000,5)
+            seq("ref","INV-{:06}")
+            uuid("v4")
+            uuid("v7",v7)
+        "#);
+        let generator = FormGenerator::new(params, 6, false);
+        let forms: Vec<Form> = std::thread::scope(|scope| {
+            let tasks: Vec<_> = (0..4).map(|task| {
+                let generator = &generator;
+                scope.spawn(move || (0..50).map(|i| generator.generate(task * 50 + i)).collect::<Vec<_>>())
+            }).collect();
+            tasks.into_iter().flat_map(|task| task.join().unwrap()).collect()
+        });
+        let column = |i: usize| -> Vec<String> {
+            forms.iter().map(|form| match &form.fields[i].1 {
+                FieldValue::Text(text) => text.clone(),
+                other => panic!("unexpected value {other:?}"),
+            }).collect()
+        };
+        let mut orders: Vec<i64> = column(0).iter().map(|id| id.parse().unwrap()).collect();
+        orders.sort();
+        assert_eq!(orders, (0..200).map(|n| 1000 + 5 * n).collect::<Vec<_>>());
+        let mut refs = column(1);
+        refs.sort();
+        assert_eq!(refs.first().map(String::as_str), Some("INV-000001"));
+        assert_eq!(refs.last().map(String::as_str), Some("INV-000200"));
+        for (i, version) in [(2, '4'), (3, '7')] {
+            let ids: HashSet<String> = column(i).into_iter().collect();
+            assert_eq!(ids.len(), 200);
+            for id in ids {
+                let groups: Vec<&str> = id.split('-').collect();
+                assert_eq!(groups.iter().map(|group| group.len()).collect::<Vec<_>>(), [8, 4, 4, 4, 12], "{id}");
+                assert!(groups[2].starts_with(version) && "89ab".contains(&groups[3][..1]), "{id}");
+            }
         }
-        params.into_iter().flatten().collect()
+        
+        // the number only depends on the request index, not on which forms were generated before
+        let params = Parameter::get_params_from_config_str(r#"counter("order_id",1000,5)"#);
+        let fresh = FormGenerator::new(params.clone(), 6, false);
+        let used = FormGenerator::new(params, 6, false);
+        for index in 0..5 {
+            used.generate(index);
+        }
+        assert_eq!(fresh.generate(5).fields, used.generate(5).fields);
+        assert_eq!(fresh.generate(5).fields, vec![("order_id".to_string(), FieldValue::Text("1025".to_string()))]);
+        
+        // uuids repeat between runs with the same seed unless they're fresh
+        let params = Parameter::get_params_from_config_str(r#"uuid("id") uuid("new",fresh) regex("r","[a-z]{8}")"#);
+        let (first, second) = (FormGenerator::new(params.clone(), 6, false), FormGenerator::new(params, 6, false));
+        assert_eq!(first.generate(0).fields[0], second.generate(0).fields[0]);
+        assert_ne!(text(&first.generate(0).fields, "new"), text(&second.generate(0).fields, "new"));
+        assert_eq!(text(&first.generate(0).fields, "r"), text(&second.generate(0).fields, "r"));
+        
+        // a run stops before a counter would wrap around
+        let params = Parameter::get_params_from_config_str(r#"
+            counter("up",9223372036854775800,3)
+            maybe(1.0,counter("down",-9223372036854775800,-5))
+        "#);
+        let generator = FormGenerator::new(params, 6, false);
+        assert_eq!(generator.limit(), Some(2));
+        assert_eq!(text(&generator.generate(1).fields, "up"), "9223372036854775803");
+        assert_eq!(text(&generator.generate(1).fields, "down"), "-9223372036854775805");
+        assert!(generator.generate(2).fields.iter().all(|(name, _)| name == "up"));
+        
+        assert!(parse_config("seq(\"ref\",\"INV-{:06\")").is_err());
+        assert!(parse_config("seq(\"ref\",\"INV\")").is_err());
+        assert_eq!(NumberFormat::parse("{{{:4}}}").unwrap().format(7), "{   7}");
+    }
+    
+    #[test]
+    fn test_person_data_is_consistent_per_locale() {
+        let params = Parameter::get_params_from_config_str(r#"
+            address([("street","street"),("city","city"),("region","region"),("postcode","zip"),("country","country")],de_DE)
+            phone("tel",DE)
+            phone("us_tel")
+            name("full",6,ja_JP)
+            first_name("first",es_ES)
+            last_name("last",es_ES)
+        "#);
+        let mut cities: HashMap<String, (String, String)> = HashMap::new();
+        for index in 0..300 {
+            let fields = Parameter::gen_param_map(&params, index, &mut form_rng(12, index));
+            let text: Vec<&str> = fields.iter().map(|(_, value)| match value {
+                FieldValue::Text(text) => text.as_str(),
+                other => panic!("unexpected value {other:?}"),
+            }).collect();
+            let [street, city, region, zip, country, tel, us_tel, full, first, last] = text[..] else { panic!("{fields:?}") };
+            assert!(street.ends_with(|c: char| c.is_ascii_digit()) && street.contains(' '), "{street}");
+            assert!(zip.len() == 5 && zip.chars().all(|c| c.is_ascii_digit()), "{zip}");
+            assert_eq!(country, "DE");
+            // a city always comes with the same region and the same start of its postcodes
+            let (known_region, known_zip) = cities.entry(city.to_string()).or_insert((region.to_string(), zip.to_string()));
+            assert_eq!((known_region.as_str(), &known_zip[..2]), (region, &zip[..2]), "{city}");
+            
+            for (tel, code) in [(tel, "+49"), (us_tel, "+1")] {
+                assert!(tel.starts_with(code) && (9..=16).contains(&tel.len()), "{tel}");
+                assert!(tel[1..].chars().all(|c| c.is_ascii_digit()), "{tel}");
+            }
+            assert_eq!(us_tel.len(), 12, "{us_tel}");
+            
+            let (family, given) = full.split_once('\u{3000}').unwrap();
+            assert!(family.chars().count() <= 6 && given.chars().count() <= 6 && !given.is_ascii(), "{full}");
+            assert!(!first.is_empty() && last.split(' ').count() >= 2, "{first} {last}");
+        }
+        assert!(cities.len() > 5);
+        assert!(parse_config("phone(\"tel\",xx_XX)").is_err());
+        assert!(parse_config("address([(\"street\",\"s\"),(\"zip\",\"z\")])").is_err());
+    }
+    
+    #[test]
+    fn test_text_word_counts_and_markov_corpus() {
+        let dir = TempConfig::new("text");
+        dir.write("corpus.txt", "The cat sat on the mat. The dog sat on the log!\nA cat saw the dog.");
+        dir.write("short.txt", "too short");
+        
+        let params = dir.load(r#"
+            text("lorem",3,30)
+            text("story",5,12,markov("corpus.txt"))
+        "#).unwrap();
+        let corpus: HashSet<&str> = "The cat sat on the mat. The dog sat on the log! A cat saw the dog.".split(' ').collect();
+        for index in 0..200 {
+            let fields = Parameter::gen_param_map(&params, index, &mut form_rng(10, index));
+            let [(_, FieldValue::Text(lorem)), (_, FieldValue::Text(story))] = &fields[..] else { panic!("{fields:?}") };
+            assert!((3..=30).contains(&lorem.split(' ').count()), "{lorem}");
+            assert!(lorem.starts_with(|c: char| c.is_ascii_uppercase()) && lorem.ends_with('.'), "{lorem}");
+            for sentence in lorem[..lorem.len() - 1].split(". ") {
+                assert!(sentence.starts_with(|c: char| c.is_ascii_uppercase()), "{lorem}");
+            }
+            let words: Vec<&str> = story.split(' ').collect();
+            assert!((5..=12).contains(&words.len()), "{story}");
+            let last = words.len() - 1;
+            assert!(words.iter().enumerate().all(|(i, word)| {
+                corpus.contains(word) || (i == last && corpus.contains(&word[..word.len() - 1]))
+            }), "{story}");
+            assert!(story.ends_with(['.', '!']), "{story}");
+        }
+        
+        let err = dir.load(r#"text("story",1,5,markov("short.txt"))"#).unwrap_err();
+        let ConfigError::Syntax { expected, .. } = err else { panic!("unexpected error {err}") };
+        assert_eq!(expected, "a file that can be loaded, `short.txt` can't be (corpus needs at least 3 words)");
+        assert!(dir.load(r#"text("story",1,5,markov("missing.txt"))"#).is_err());
+        assert!(parse_config("text(\"t\",5,1)").is_err());
+    }
+    
+    #[test]
+    fn test_passwords_follow_their_policy() {
+        let params = Parameter::get_params_from_config_str(r#"
+            password("password",10,16,[upper,digits(2),symbols,forbid("\"'\\<>")])
+            static("password_confirm",ref("password"))
+        "#);
+        let count = |password: &str, class: fn(&char) -> bool| password.chars().filter(class).count();
+        for index in 0..300 {
+            let fields = Parameter::gen_param_map(&params, index, &mut form_rng(13, index));
+            let [(_, FieldValue::Text(password)), (_, FieldValue::Text(confirm))] = &fields[..] else { panic!("{fields:?}") };
+            assert_eq!(password, confirm);
+            assert!((10..=16).contains(&password.chars().count()), "{password}");
+            assert!(count(password, char::is_ascii_uppercase) >= 1, "{password}");
+            assert!(count(password, char::is_ascii_digit) >= 2, "{password}");
+            assert!(count(password, char::is_ascii_punctuation) >= 1, "{password}");
+            assert!(!password.contains(['"', '\'', '\\', '<', '>']), "{password}");
+        }
+        
+        let generator = FormGenerator::new(params, 13, false).with_invalid_values(true);
+        let weak = (0..200).map(|index| generator.generate(index)).filter_map(|form| {
+            let violation = form.violation?;
+            (violation.kind == ViolationKind::WeakPassword).then_some(form.fields)
+        }).collect::<Vec<_>>();
+        assert!(!weak.is_empty());
+        for fields in weak {
+            let FieldValue::Text(password) = &fields[0].1 else { panic!() };
+            assert!(
+                password.chars().count() < 10
+                    || count(password, char::is_ascii_uppercase) == 0
+                    || count(password, char::is_ascii_digit) < 2
+                    || count(password, char::is_ascii_punctuation) == 0,
+                "{password}"
+            );
+        }
+        
+        assert!(parse_config("password(\"pw\",4,6,[digits(4),upper(3)])").is_err());
+        assert!(parse_config("password(\"pw\",4,6,[digits,forbid(\"0123456789\")])").is_err());
+        assert!(parse_config("password(\"pw\",4,6,[numbers])").is_err());
+    }
+    
+    #[test]
+    fn test_identifiers_pass_or_fail_their_checks() {
+        let params = Parameter::get_params_from_config_str(r#"
+            card("visa",visa)
+            card("amex",amex)
+            iban("iban",GB)
+            iban("iban_de")
+            isbn("isbn")
+            ean("ean")
+            card("bad_card",mastercard,invalid)
+            iban("bad_iban",FR,invalid)
+            isbn("bad_isbn",invalid)
+            ean("bad_ean",invalid)
+        "#);
+        // every second digit from the right doubled, with the digits of the products summed
+        let luhn = |number: &str| number.chars().rev().filter_map(|c| c.to_digit(10)).enumerate()
+            .map(|(i, digit)| match i % 2 {
+                1 if digit * 2 > 9 => digit * 2 - 9,
+                1 => digit * 2,
+                _ => digit,
+            })
+            .sum::<u32>() % 10 == 0;
+        let ean = |number: &str| checksum::ean_check_digit(&number[..12]).to_string() == number[12..];
+        let iban = |number: &str| checksum::iban_check_digits(&number[..2], &number[4..]) % 97 == number[2..4].parse::<u32>().unwrap() % 97;
+        for index in 0..300 {
+            let fields = Parameter::gen_param_map(&params, index, &mut form_rng(17, index));
+            let value = |name: &str| text(&fields, name);
+            let (visa, amex) = (value("visa"), value("amex"));
+            assert!(CardBrand::Visa.test_numbers().contains(&visa.as_str()) && luhn(&visa), "{visa}");
+            assert!(CardBrand::Amex.test_numbers().contains(&amex.as_str()) && luhn(&amex), "{amex}");
+            let (gb, de) = (value("iban"), value("iban_de"));
+            assert!(gb.len() == 22 && gb.starts_with("GB") && iban(&gb), "{gb}");
+            assert!(de.len() == 22 && de.starts_with("DE") && iban(&de), "{de}");
+            let isbn = value("isbn");
+            assert!(isbn.len() == 13 && (isbn.starts_with("978") || isbn.starts_with("979")) && ean(&isbn), "{isbn}");
+            assert!(ean(&value("ean")));
+            
+            let bad_card = value("bad_card");
+            assert!(!luhn(&bad_card), "{bad_card}");
+            assert!(CardBrand::Mastercard.test_numbers().iter().any(|number| number[..15] == bad_card[..15]), "{bad_card}");
+            assert!(!iban(&value("bad_iban")));
+            assert!(!ean(&value("bad_isbn")));
+            assert!(!ean(&value("bad_ean")));
+        }
+        
+        let generator = FormGenerator::new(params, 17, false).with_invalid_values(true);
+        let broken = (0..200).filter_map(|index| generator.generate(index).violation)
+            .filter(|violation| violation.kind == ViolationKind::BadChecksum)
+            .count();
+        assert!(broken > 0);
+        
+        assert!(parse_config("card(\"cc\",diners)").is_err());
+        assert!(parse_config("iban(\"iban\",US)").is_err());
+        assert!(parse_config("ean(\"ean\",valid)").is_err());
+    }
+    
+    #[test]
+    fn test_lengths_follow_their_distribution() {
+        let dir = TempConfig::new("lengths");
+        dir.write("sizes.csv", "# length,weight\n3,8\n\n10..12,2\n50,0\n");
+        dir.write("broken.csv", "3,8\nten,2\n");
+        dir.write("empty.csv", "# nothing\n3,0\n");
+        
+        let params = dir.load(r#"
+            string("bio",100,histogram("sizes.csv"))
+            string("skewed",1000,zipf(1.2))
+            name("name",20,fixed(4))
+            name("local",20,en_US,fixed(4))
+            email("email",["x.com"],fixed(7))
+            text("body",1,40,fixed(9))
+            password("pw",8,64,[digits],zipf(2))
+            file("blob",bytes(1,1000,normal(100,10)))
+        "#).unwrap();
+        let (mut short_bios, mut short_skewed, mut short_passwords) = (0, 0, 0);
+        for index in 0..300 {
+            let fields = Parameter::gen_param_map(&params, index, &mut form_rng(19, index));
+            let text = |name: &str| text(&fields, name);
+            let bio = text("bio").chars().count();
+            assert!([3, 10, 11, 12].contains(&bio), "{bio}");
+            short_bios += (bio == 3) as usize;
+            let skewed = text("skewed").chars().count();
+            assert!((1..=1000).contains(&skewed));
+            short_skewed += (skewed <= 10) as usize;
+            assert_eq!(text("name").chars().count(), 9);
+            assert!(text("local").split(' ').all(|part| (1..=4).contains(&part.chars().count())), "{}", text("local"));
+            assert_eq!(text("email").split('@').next().unwrap().len(), 7);
+            assert_eq!(text("body").split(' ').count(), 9);
+            let password = text("pw").chars().count();
+            assert!((8..=64).contains(&password));
+            short_passwords += (password == 8) as usize;
+            let Some((_, FieldValue::File { data, .. })) = fields.iter().find(|(field, _)| field == "blob") else { panic!() };
+            assert!((40..=160).contains(&data.len()), "{}", data.len());
+        }
+        // uniform lengths would make these a few percent at most
+        assert!(short_bios > 180, "{short_bios}");
+        assert!(short_skewed > 120, "{short_skewed}");
+        assert!(short_passwords > 120, "{short_passwords}");
+        
+        // the longest max length there is still gives lengths in range, whatever the distribution
+        let generator = FormGenerator::new(dir.load(r#"
+            string("uniform",4194304)
+            string("zipf",4194304,zipf(2))
+            string("normal",4194304,normal(5,2))
+            name("fixed",4194304,fixed(4))
+        "#).unwrap(), 19, false);
+        for index in 0..5 {
+            let fields = generator.generate(index).fields;
+            assert!((1..=4194304).contains(&text(&fields, "uniform").len()));
+            assert!(["zipf", "normal"].iter().all(|name| !text(&fields, name).is_empty()), "{fields:?}");
+            assert_eq!(text(&fields, "fixed").chars().count(), 9);
+        }
+        assert!(dir.load(r#"string("s",4194305)"#).is_err());
+        assert!(dir.load(r#"string("s",9223372036854775807)"#).is_err());
+        assert!(dir.load(r#"name("n",18446744073709551615,fixed(4))"#).is_err());
+        
+        let err = dir.load(r#"string("bio",100,histogram("broken.csv"))"#).unwrap_err();
+        let ConfigError::Syntax { expected, .. } = err else { panic!("unexpected error {err}") };
+        assert_eq!(expected, "a file that can be loaded, `broken.csv` can't be (line 2: expected `value,weight` or `low..high,weight`)");
+        let err = dir.load("static(\"a\",\"b\")\n  int(\"n\",1,10,histogram(\"nope.csv\"))").unwrap_err();
+        let ConfigError::Syntax { path, line, column, snippet, expected } = err else { panic!("unexpected error {err}") };
+        assert_eq!((path, line, column), (dir.path("form.conf"), 2, 3));
+        assert_eq!(snippet, "  int(\"n\",1,10,histogram(\"nope.csv\"))");
+        assert!(expected.starts_with("a file that can be loaded, `nope.csv` can't be ("), "{expected}");
+        assert!(dir.load(r#"int("n",1,10,histogram("empty.csv"))"#).is_err());
+        assert!(dir.load(r#"text("t",1,10,lorem,histogram("missing.csv"))"#).is_err());
     }
 }
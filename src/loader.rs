@@ -0,0 +1,145 @@
+use std::{collections::HashMap, fs, path::{Path, PathBuf}};
+
+use chrono::NaiveDateTime;
+use nom::Offset;
+
+use crate::{ConfigError, Parameter, parser::{Definitions, Statement, parse_statements}};
+
+/// state shared by a config file and all the files it includes
+struct Loader {
+    /// what `now` relative dates are pinned to
+    now: NaiveDateTime,
+    /// directory of the outermost config file, paths in messages are relative to it
+    root: PathBuf,
+    /// files being loaded, outermost first, as (canonical path, path as joined) to catch include cycles
+    stack: Vec<(PathBuf, PathBuf)>,
+    /// every file loaded so far by canonical path, with where it was first included and whether it
+    /// only defines fragments. a file of fragments included again, e.g. by several included files,
+    /// is skipped. one that sends fields can only be included once
+    loaded: HashMap<PathBuf, Included>,
+    definitions: Definitions,
+    params: Vec<Parameter>,
+}
+
+struct Included {
+    /// `path:line` of the first include, for messages
+    site: String,
+    only_fragments: bool,
+}
+
+/// reads the config file at `path` along with everything it includes. file paths in each file are
+/// relative to that file
+pub(crate) fn load(path: &Path) -> Result<Vec<Parameter>, ConfigError> {
+    let mut loader = Loader {
+        now: chrono::Local::now().naive_local(),
+        root: path.parent().unwrap_or(Path::new("")).to_path_buf(),
+        stack: Vec::new(),
+        loaded: HashMap::new(),
+        definitions: Definitions::default(),
+        params: Vec::new(),
+    };
+    loader.load_file(path)?;
+    Ok(loader.params)
+}
+
+impl Loader {
+    /// loads the file at `path` and gives whether it only defines fragments
+    fn load_file(&mut self, path: &Path) -> Result<bool, ConfigError> {
+        let io_error = |source| ConfigError::Io { path: path.to_path_buf(), source };
+        let contents = fs::read_to_string(path).map_err(io_error)?;
+        let canonical = fs::canonicalize(path).map_err(io_error)?;
+        self.stack.push((canonical, path.to_path_buf()));
+        let mut only_fragments = true;
+
+        let syntax_error = |at: &str, expected: String| ConfigError::syntax(path, &contents, contents.offset(at), expected);
+        let statements = parse_statements(&contents).map_err(|err| {
+            ConfigError::syntax(path, &contents, err.offset_in(&contents), err.expected.unwrap_or_else(|| "a generator".to_string()))
+        })?;
+        let base = path.parent().unwrap_or(Path::new(""));
+        for (at, statement) in statements {
+            let statement = match statement {
+                Statement::Include(include) => {
+                    let included = base.join(include);
+                    let canonical = fs::canonicalize(&included).map_err(|err| syntax_error(
+                        at,
+                        format!("an include of a file that can be read, `{}` can't be ({err})", self.relative(&included))
+                    ))?;
+                    if let Some(cycle) = self.cycle(&canonical, &included) {
+                        return Err(syntax_error(at, format!("an include that doesn't lead back to a file that includes it ({cycle})")));
+                    }
+                    match self.loaded.get(&canonical) {
+                        Some(Included { only_fragments: true, .. }) => {}
+                        Some(Included { site, .. }) => return Err(syntax_error(at, format!(
+                            "an include of a file that isn't included yet, `{}` sends fields and is already included at {site}",
+                            self.relative(&included)
+                        ))),
+                        None => {
+                            let site = self.site(path, &contents, at);
+                            let included_only_fragments = self.load_file(&included)?;
+                            self.loaded.insert(canonical, Included { site, only_fragments: included_only_fragments });
+                            only_fragments &= included_only_fragments;
+                        }
+                    }
+                    continue;
+                }
+                // uses were resolved where their fragment was defined
+                Statement::Param(mut param) => {
+                    only_fragments = false;
+                    param.resolve(base, self.now, &|expected| syntax_error(at, expected))
+                        .map_err(|err| self.file_error(err, |expected| syntax_error(at, expected)))?;
+                    Statement::Param(param)
+                }
+                Statement::Fragment { name, mut body } => {
+                    for statement in &mut body {
+                        if let Statement::Param(param) = statement {
+                            param.resolve(base, self.now, &|expected| syntax_error(at, expected))
+                                .map_err(|err| self.file_error(err, |expected| syntax_error(at, expected)))?;
+                        }
+                    }
+                    Statement::Fragment { name, body }
+                }
+                statement @ Statement::Use { .. } => {
+                    only_fragments = false;
+                    statement
+                }
+            };
+            let site = self.site(path, &contents, at);
+            self.definitions.add(statement, &site, &mut self.params).map_err(|expected| syntax_error(at, expected))?;
+        }
+        self.stack.pop();
+        Ok(only_fragments)
+    }
+
+    /// the chain of includes, e.g. `a.form -> b.form -> a.form`, if including `path` would load a
+    /// file that is already being loaded
+    fn cycle(&self, canonical: &Path, path: &Path) -> Option<String> {
+        let start = self.stack.iter().position(|(loading, _)| loading == canonical)?;
+        let chain: Vec<String> = self.stack[start..].iter()
+            .map(|(_, joined)| joined.as_path())
+            .chain([path])
+            .map(|path| self.relative(path))
+            .collect();
+        Some(chain.join(" -> "))
+    }
+
+    /// a file a generator refers to that couldn't be loaded, as an error pointing at the generator
+    fn file_error(&self, err: ConfigError, syntax_error: impl Fn(String) -> ConfigError) -> ConfigError {
+        match err {
+            ConfigError::Io { path, source } => {
+                syntax_error(format!("a file that can be loaded, `{}` can't be ({source})", self.relative(&path)))
+            }
+            err => err,
+        }
+    }
+
+    /// `path:line` of `at`, a position in `contents`, which is the file at `path`
+    fn site(&self, path: &Path, contents: &str, at: &str) -> String {
+        let line = contents[..contents.offset(at)].matches('\n').count() + 1;
+        format!("{}:{line}", self.relative(path))
+    }
+
+    /// `path` relative to the directory of the outermost config file, for messages
+    fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root).unwrap_or(path).display().to_string()
+    }
+}
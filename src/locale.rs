@@ -0,0 +1,437 @@
+use rand::{Rng, rngs::StdRng};
+
+/// country whose names, addresses and phone numbers the person generators produce. written as
+/// the locale (`de_DE`) or just the country code (`DE`) in a config
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Locale {
+    #[default]
+    EnUs,
+    EnGb,
+    DeDe,
+    FrFr,
+    EsEs,
+    JaJp,
+}
+
+/// `first_name(...)` or `last_name(...)`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NamePart {
+    First,
+    /// both surnames where two are used, as in Spain
+    Last,
+}
+
+/// line of an address a field of `address(...)` gets
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AddressPart {
+    /// street and house number, in the order the country writes them
+    Street,
+    City,
+    /// state, county, prefecture, ..
+    Region,
+    Postcode,
+    /// ISO 3166 code, e.g. `US`
+    Country,
+}
+
+impl AddressPart {
+    pub const NAMES: &[(&str, AddressPart)] = &[
+        ("street", AddressPart::Street),
+        ("city", AddressPart::City),
+        ("region", AddressPart::Region),
+        ("postcode", AddressPart::Postcode),
+        ("country", AddressPart::Country),
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::NAMES.iter().find(|(part, _)| *part == name).map(|(_, part)| *part)
+    }
+}
+
+/// a made up address in a city that exists, with that city's region and a postcode from it
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Address {
+    pub street: String,
+    pub city: &'static str,
+    pub region: &'static str,
+    pub postcode: String,
+    pub country: &'static str,
+}
+
+impl Address {
+    pub fn part(&self, part: AddressPart) -> &str {
+        match part {
+            AddressPart::Street => &self.street,
+            AddressPart::City => self.city,
+            AddressPart::Region => self.region,
+            AddressPart::Postcode => &self.postcode,
+            AddressPart::Country => self.country,
+        }
+    }
+}
+
+impl Locale {
+    pub const ALL: &[Locale] = &[Locale::EnUs, Locale::EnGb, Locale::DeDe, Locale::FrFr, Locale::EsEs, Locale::JaJp];
+
+    /// `en_US`, `de_DE`, ..
+    pub fn code(self) -> &'static str {
+        self.data().code
+    }
+
+    /// reads a locale such as `de_DE`, or a country code such as `DE`
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|locale| {
+            let code = locale.code();
+            name == code || name == &code[3..]
+        })
+    }
+
+    fn data(self) -> &'static LocaleData {
+        match self {
+            Locale::EnUs => &EN_US,
+            Locale::EnGb => &EN_GB,
+            Locale::DeDe => &DE_DE,
+            Locale::FrFr => &FR_FR,
+            Locale::EsEs => &ES_ES,
+            Locale::JaJp => &JA_JP,
+        }
+    }
+
+    /// digits of the international calling code, without the `+`
+    pub(crate) fn calling_code(self) -> &'static str {
+        self.data().calling_code
+    }
+
+    pub(crate) fn name(self, rng: &mut StdRng, part: NamePart) -> String {
+        let data = self.data();
+        match part {
+            NamePart::First => pick(rng, data.first_names).to_string(),
+            NamePart::Last if data.order == NameOrder::GivenTwoFamilies => {
+                format!("{} {}", pick(rng, data.last_names), pick(rng, data.last_names))
+            }
+            NamePart::Last => pick(rng, data.last_names).to_string(),
+        }
+    }
+
+    /// first and last name in the order the country writes them, each part at most `max_len`
+    /// characters long
+    pub(crate) fn full_name(self, rng: &mut StdRng, max_len: usize) -> String {
+        let data = self.data();
+        let mut part = |names: &[&str]| {
+            let fitting: Vec<&str> = names.iter().copied().filter(|name| name.chars().count() <= max_len).collect();
+            match fitting.is_empty() {
+                false => pick(rng, &fitting).to_string(),
+                true => pick(rng, names).chars().take(max_len.max(1)).collect(),
+            }
+        };
+        match data.order {
+            NameOrder::GivenFamily => format!("{} {}", part(data.first_names), part(data.last_names)),
+            NameOrder::GivenTwoFamilies => {
+                format!("{} {} {}", part(data.first_names), part(data.last_names), part(data.last_names))
+            }
+            // with an ideographic space, as it's usually written
+            NameOrder::FamilyGiven => format!("{}\u{3000}{}", part(data.last_names), part(data.first_names)),
+        }
+    }
+
+    pub(crate) fn address(self, rng: &mut StdRng) -> Address {
+        let data = self.data();
+        let city = pick(rng, data.cities);
+        let street = pick(rng, data.streets);
+        let street = match data.street_format {
+            StreetFormat::NumberFirst => format!("{} {street}", rng.random_range(1..=400)),
+            StreetFormat::NumberAfter(separator) => format!("{street}{separator}{}", rng.random_range(1..=200)),
+            StreetFormat::Chome => format!(
+                "{street}{}-{}-{}",
+                rng.random_range(1..=9), rng.random_range(1..=30), rng.random_range(1..=20)
+            ),
+        };
+        Address {
+            street,
+            city: city.name,
+            region: city.region,
+            postcode: fill_pattern(rng, city.postcode),
+            country: &data.code[3..],
+        }
+    }
+
+    /// E.164 number, `+` and the calling code followed by the area code of a city and a subscriber
+    /// number, without spaces
+    pub(crate) fn phone(self, rng: &mut StdRng) -> String {
+        let data = self.data();
+        let city = pick(rng, data.cities);
+        format!("+{}{}{}", data.calling_code, city.area_code, fill_pattern(rng, city.subscriber))
+    }
+}
+
+fn pick<'a, T>(rng: &mut StdRng, items: &'a [T]) -> &'a T {
+    &items[rng.random_range(0..items.len())]
+}
+
+/// replaces `#` with any digit, `*` with a digit from 2 to 9 and `?` with a letter used in UK
+/// postcodes. everything else is kept
+fn fill_pattern(rng: &mut StdRng, pattern: &str) -> String {
+    const LETTERS: &[u8] = b"ABDEFGHJLNPQRSTUWXYZ";
+    pattern.chars().map(|c| match c {
+        '#' => char::from(b'0' + rng.random_range(0..10)),
+        '*' => char::from(b'0' + rng.random_range(2..10)),
+        '?' => char::from(LETTERS[rng.random_range(0..LETTERS.len())]),
+        c => c,
+    }).collect()
+}
+
+#[derive(Debug, PartialEq)]
+enum NameOrder {
+    GivenFamily,
+    /// a given name and two surnames
+    GivenTwoFamilies,
+    FamilyGiven,
+}
+
+#[derive(Debug)]
+enum StreetFormat {
+    /// `221 Baker Street`
+    NumberFirst,
+    /// `Hauptstraße 12` or `Calle Mayor, 12`, with the text between the street and the number
+    NumberAfter(&'static str),
+    /// district followed by chome, block and building numbers, `銀座4-5-6`
+    Chome,
+}
+
+#[derive(Debug)]
+struct City {
+    name: &'static str,
+    region: &'static str,
+    /// pattern for `fill_pattern`
+    postcode: &'static str,
+    area_code: &'static str,
+    /// pattern for `fill_pattern`, the digits after the area code
+    subscriber: &'static str,
+}
+
+#[derive(Debug)]
+struct LocaleData {
+    code: &'static str,
+    calling_code: &'static str,
+    first_names: &'static [&'static str],
+    last_names: &'static [&'static str],
+    order: NameOrder,
+    streets: &'static [&'static str],
+    street_format: StreetFormat,
+    cities: &'static [City],
+}
+
+const fn city(
+    name: &'static str,
+    region: &'static str,
+    postcode: &'static str,
+    area_code: &'static str,
+    subscriber: &'static str,
+) -> City {
+    City { name, region, postcode, area_code, subscriber }
+}
+
+static EN_US: LocaleData = LocaleData {
+    code: "en_US",
+    calling_code: "1",
+    first_names: &[
+        "James", "Mary", "Robert", "Patricia", "John", "Jennifer", "Michael", "Linda", "David",
+        "Elizabeth", "William", "Barbara", "Richard", "Susan", "Joseph", "Jessica", "Thomas", "Sarah",
+        "Christopher", "Karen", "Daniel", "Nancy", "Matthew", "Lisa", "Anthony", "Betty", "José",
+        "Mei", "Aaliyah", "DeShawn", "Mary-Kate", "Zoë",
+    ],
+    last_names: &[
+        "Smith", "Johnson", "Williams", "Brown", "Jones", "Garcia", "Miller", "Davis", "Rodriguez",
+        "Martinez", "Hernandez", "Lopez", "Gonzalez", "Wilson", "Anderson", "Thomas", "Taylor",
+        "Moore", "Jackson", "Martin", "Lee", "Perez", "Thompson", "White", "Harris", "Sanchez",
+        "Clark", "Nguyen", "O'Brien", "McDonald", "Van Buren", "Smith-Jones",
+    ],
+    order: NameOrder::GivenFamily,
+    streets: &[
+        "Main Street", "Oak Avenue", "Maple Street", "Park Avenue", "Cedar Lane", "Elm Street",
+        "Washington Avenue", "Lake Drive", "Hillcrest Road", "Sunset Boulevard", "Pine Street",
+        "2nd Street", "Church Street", "Highland Avenue", "Jefferson Street", "Martin Luther King Jr Boulevard",
+    ],
+    street_format: StreetFormat::NumberFirst,
+    cities: &[
+        city("New York", "NY", "100##", "212", "*######"),
+        city("Los Angeles", "CA", "900##", "213", "*######"),
+        city("Chicago", "IL", "606##", "312", "*######"),
+        city("Houston", "TX", "770##", "713", "*######"),
+        city("Phoenix", "AZ", "850##", "602", "*######"),
+        city("Philadelphia", "PA", "191##", "215", "*######"),
+        city("San Antonio", "TX", "782##", "210", "*######"),
+        city("San Diego", "CA", "921##", "619", "*######"),
+        city("Dallas", "TX", "752##", "214", "*######"),
+        city("Seattle", "WA", "981##", "206", "*######"),
+        city("Denver", "CO", "802##", "303", "*######"),
+        city("Boston", "MA", "021##", "617", "*######"),
+        city("Atlanta", "GA", "303##", "404", "*######"),
+        city("Miami", "FL", "331##", "305", "*######"),
+        city("Portland", "OR", "972##", "503", "*######"),
+    ],
+};
+
+static EN_GB: LocaleData = LocaleData {
+    code: "en_GB",
+    calling_code: "44",
+    first_names: &[
+        "Oliver", "Amelia", "George", "Isla", "Harry", "Ava", "Jack", "Mia", "Charlie", "Olivia",
+        "Thomas", "Emily", "Jacob", "Poppy", "Alfie", "Sophie", "Oscar", "Grace", "William", "Freya",
+        "Muhammad", "Siobhan", "Rhys", "Niamh",
+    ],
+    last_names: &[
+        "Smith", "Jones", "Taylor", "Brown", "Williams", "Wilson", "Johnson", "Davies", "Robinson",
+        "Wright", "Thompson", "Evans", "Walker", "White", "Roberts", "Green", "Hall", "Wood",
+        "Jackson", "Clarke", "Patel", "Khan", "MacDonald", "O'Neill", "Bowes-Lyon",
+    ],
+    order: NameOrder::GivenFamily,
+    streets: &[
+        "High Street", "Station Road", "Church Lane", "Victoria Road", "Green Lane", "Manor Road",
+        "Park Road", "Queens Road", "The Crescent", "Mill Lane", "Kings Road", "School Lane",
+        "St John's Road", "Albert Terrace",
+    ],
+    street_format: StreetFormat::NumberFirst,
+    cities: &[
+        city("London", "Greater London", "N# #??", "20", "7#######"),
+        city("Manchester", "Greater Manchester", "M## #??", "161", "*######"),
+        city("Birmingham", "West Midlands", "B## #??", "121", "*######"),
+        city("Leeds", "West Yorkshire", "LS# #??", "113", "*######"),
+        city("Glasgow", "Scotland", "G## #??", "141", "*######"),
+        city("Edinburgh", "Scotland", "EH# #??", "131", "*######"),
+        city("Bristol", "Bristol", "BS# #??", "117", "*######"),
+        city("Cardiff", "Wales", "CF## #??", "29", "2#######"),
+        city("Liverpool", "Merseyside", "L## #??", "151", "*######"),
+        city("Belfast", "Northern Ireland", "BT# #??", "28", "9#######"),
+    ],
+};
+
+static DE_DE: LocaleData = LocaleData {
+    code: "de_DE",
+    calling_code: "49",
+    first_names: &[
+        "Lukas", "Anna", "Leon", "Lea", "Finn", "Hannah", "Jonas", "Mia", "Paul", "Emma", "Felix",
+        "Sophie", "Maximilian", "Marie", "Elias", "Lena", "Jürgen", "Jörg", "Ursula", "Günther",
+        "Anneliese", "Björn", "Züleyha", "Mehmet", "Hans-Peter",
+    ],
+    last_names: &[
+        "Müller", "Schmidt", "Schneider", "Fischer", "Weber", "Meyer", "Wagner", "Becker", "Schulz",
+        "Hoffmann", "Schäfer", "Koch", "Bauer", "Richter", "Klein", "Wolf", "Schröder", "Neumann",
+        "Schwarz", "Zimmermann", "Braun", "Krüger", "Hofmann", "Yılmaz", "von Weizsäcker", "Groß",
+    ],
+    order: NameOrder::GivenFamily,
+    streets: &[
+        "Hauptstraße", "Schulstraße", "Gartenstraße", "Bahnhofstraße", "Dorfstraße", "Bergstraße",
+        "Lindenstraße", "Birkenweg", "Am Markt", "Goethestraße", "Schillerstraße", "Kirchplatz",
+        "Mühlenweg", "Friedrich-Ebert-Straße",
+    ],
+    street_format: StreetFormat::NumberAfter(" "),
+    cities: &[
+        city("Berlin", "Berlin", "10###", "30", "*#######"),
+        city("Hamburg", "Hamburg", "20###", "40", "*#######"),
+        city("München", "Bayern", "80###", "89", "*#######"),
+        city("Köln", "Nordrhein-Westfalen", "50###", "221", "*######"),
+        city("Frankfurt am Main", "Hessen", "60###", "69", "*#######"),
+        city("Stuttgart", "Baden-Württemberg", "70###", "711", "*######"),
+        city("Düsseldorf", "Nordrhein-Westfalen", "40###", "211", "*######"),
+        city("Leipzig", "Sachsen", "04###", "341", "*######"),
+        city("Dresden", "Sachsen", "01###", "351", "*######"),
+        city("Nürnberg", "Bayern", "90###", "911", "*######"),
+    ],
+};
+
+static FR_FR: LocaleData = LocaleData {
+    code: "fr_FR",
+    calling_code: "33",
+    first_names: &[
+        "Gabriel", "Louise", "Léo", "Jade", "Raphaël", "Emma", "Arthur", "Chloé", "Louis", "Inès",
+        "Jules", "Léa", "Hugo", "Manon", "Lucas", "Zoé", "Adam", "Anaïs", "François", "Hélène",
+        "Benoît", "Maëlle", "Noémie", "Jean-Baptiste",
+    ],
+    last_names: &[
+        "Martin", "Bernard", "Thomas", "Petit", "Robert", "Richard", "Durand", "Dubois", "Moreau",
+        "Laurent", "Simon", "Michel", "Lefèvre", "Leroy", "Roux", "David", "Bertrand", "Morel",
+        "Fournier", "Girard", "Bonnet", "Dupont", "Lambert", "Fontaine", "d'Arcy", "Le Goff",
+    ],
+    order: NameOrder::GivenFamily,
+    streets: &[
+        "rue de la Paix", "rue Victor Hugo", "rue de la République", "boulevard Saint-Michel",
+        "rue Pasteur", "place de la Mairie", "rue du Moulin", "avenue Jean Jaurès", "rue de l'Église",
+        "chemin des Vignes", "impasse des Lilas", "allée des Tilleuls",
+    ],
+    street_format: StreetFormat::NumberFirst,
+    cities: &[
+        city("Paris", "Île-de-France", "750##", "1", "########"),
+        city("Marseille", "Provence-Alpes-Côte d'Azur", "130##", "4", "########"),
+        city("Lyon", "Auvergne-Rhône-Alpes", "6900#", "4", "########"),
+        city("Toulouse", "Occitanie", "310##", "5", "########"),
+        city("Nice", "Provence-Alpes-Côte d'Azur", "060##", "4", "########"),
+        city("Nantes", "Pays de la Loire", "440##", "2", "########"),
+        city("Strasbourg", "Grand Est", "670##", "3", "########"),
+        city("Montpellier", "Occitanie", "340##", "4", "########"),
+        city("Bordeaux", "Nouvelle-Aquitaine", "330##", "5", "########"),
+        city("Lille", "Hauts-de-France", "590##", "3", "########"),
+    ],
+};
+
+static ES_ES: LocaleData = LocaleData {
+    code: "es_ES",
+    calling_code: "34",
+    first_names: &[
+        "Hugo", "Lucía", "Martín", "Sofía", "Pablo", "María", "Alejandro", "Martina", "Lucas",
+        "Paula", "Daniel", "Julia", "Mateo", "Valeria", "Álvaro", "Carmen", "Javier", "Núria",
+        "Jordi", "Begoña", "Iñaki", "Ainhoa", "José Luis", "María José",
+    ],
+    last_names: &[
+        "García", "Rodríguez", "González", "Fernández", "López", "Martínez", "Sánchez", "Pérez",
+        "Gómez", "Martín", "Jiménez", "Ruiz", "Hernández", "Díaz", "Moreno", "Muñoz", "Álvarez",
+        "Romero", "Alonso", "Gutiérrez", "Navarro", "Torres", "Domínguez", "Vázquez", "de la Fuente",
+    ],
+    order: NameOrder::GivenTwoFamilies,
+    streets: &[
+        "Calle Mayor", "Calle Real", "Avenida de la Constitución", "Plaza de España", "Calle del Sol",
+        "Calle San Juan", "Paseo de Gracia", "Gran Vía", "Calle de Alcalá", "Avenida Diagonal",
+        "Calle Nueva", "Calle de la Iglesia",
+    ],
+    street_format: StreetFormat::NumberAfter(", "),
+    cities: &[
+        city("Madrid", "Comunidad de Madrid", "280##", "91", "*######"),
+        city("Barcelona", "Cataluña", "080##", "93", "*######"),
+        city("Valencia", "Comunidad Valenciana", "460##", "96", "*######"),
+        city("Sevilla", "Andalucía", "410##", "95", "*######"),
+        city("Zaragoza", "Aragón", "500##", "976", "*#####"),
+        city("Málaga", "Andalucía", "290##", "95", "*######"),
+        city("Bilbao", "País Vasco", "480##", "94", "*######"),
+        city("Palma", "Islas Baleares", "070##", "971", "*#####"),
+        city("Murcia", "Región de Murcia", "300##", "968", "*#####"),
+        city("A Coruña", "Galicia", "150##", "981", "*#####"),
+    ],
+};
+
+static JA_JP: LocaleData = LocaleData {
+    code: "ja_JP",
+    calling_code: "81",
+    first_names: &[
+        "翔太", "陽菜", "蓮", "結衣", "大翔", "葵", "湊", "凛", "悠真", "美咲", "健太", "さくら",
+        "拓海", "愛子", "直樹", "由美",
+    ],
+    last_names: &[
+        "佐藤", "鈴木", "高橋", "田中", "伊藤", "渡辺", "山本", "中村", "小林", "加藤", "吉田",
+        "山田", "佐々木", "山口", "松本", "井上",
+    ],
+    order: NameOrder::FamilyGiven,
+    streets: &["西新宿", "丸の内", "銀座", "梅田", "栄", "中央", "本町", "桜木町", "大通西", "天神"],
+    street_format: StreetFormat::Chome,
+    cities: &[
+        city("新宿区", "東京都", "160-####", "3", "*#######"),
+        city("千代田区", "東京都", "100-####", "3", "*#######"),
+        city("大阪市北区", "大阪府", "530-####", "6", "*#######"),
+        city("名古屋市中区", "愛知県", "460-####", "52", "*######"),
+        city("横浜市西区", "神奈川県", "220-####", "45", "*######"),
+        city("札幌市中央区", "北海道", "060-####", "11", "*######"),
+        city("福岡市中央区", "福岡県", "810-####", "92", "*######"),
+        city("京都市下京区", "京都府", "600-####", "75", "*######"),
+        city("仙台市青葉区", "宮城県", "980-####", "22", "*######"),
+        city("神戸市中央区", "兵庫県", "650-####", "78", "*######"),
+    ],
+};
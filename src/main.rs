@@ -1,6 +1,7 @@
 use std::time::Duration;
-use form_loadtester::{Args, Commands, crtsh::Scraper, Parameter, spammer::Sender};
+use form_loadtester::{Args, Commands, crtsh::Scraper, Parameter, spammer::Sender, FormGenerator, preview};
 use clap::Parser;
+use rand::Rng;
 
 #[tokio::main]
 async fn main() {
@@ -19,14 +20,39 @@ async fn main() {
                 println!("{domain}");
             }
         }
-        Commands::Spam { domain, max_open, config} => {
-            let params: &'static mut Vec<Parameter> = Box::leak(Box::new(Parameter::get_params_from_config(&config).unwrap()));
-            let form_builder = ||  {
-                let params = Parameter::gen_param_map(params);
-                params
+        Commands::Spam { domain, max_open, config, shuffle, seed, invalid } => {
+            let generator = load_generator(&config, seed, shuffle, invalid);
+            println!("[*] seed {}", generator.seed());
+            let generator: &'static FormGenerator = Box::leak(Box::new(generator));
+            let form_builder = |index|  {
+                generator.try_generate(index)
             };
-            let sender = Sender::new(Duration::from_secs(20), &domain, form_builder, max_open).unwrap();
+            let sender = Sender::new(Duration::from_secs(20), &domain, form_builder, max_open, generator.seed(), generator.limit()).unwrap();
             sender.begin().await.unwrap();
+            report_exhausted(generator);
         },
+        Commands::Preview { config, count, start, format, shuffle, seed, invalid } => {
+            let generator = load_generator(&config, seed, shuffle, invalid);
+            eprintln!("[*] seed {}", generator.seed());
+            preview::write_forms(&mut std::io::stdout().lock(), &generator, start, count, format)
+                .expect("failed to write forms");
+            report_exhausted(&generator);
+        },
+    }
+}
+
+/// parses the config, exiting with the error message if it's invalid
+fn load_generator(config: &str, seed: Option<u64>, shuffle: bool, invalid: bool) -> FormGenerator {
+    let params = Parameter::get_params_from_config(config).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(1);
+    });
+    FormGenerator::new(params, seed.unwrap_or_else(|| rand::rng().random()), shuffle)
+        .with_invalid_values(invalid)
+}
+
+fn report_exhausted(generator: &FormGenerator) {
+    if let Some(fields) = generator.exhausted() {
+        eprintln!("[*] unique(...) ran out of unused values for `{fields}`");
     }
 }
@@ -0,0 +1,58 @@
+use rand::{Rng, rngs::StdRng};
+
+use crate::{FieldValue, FormFields, Violation};
+
+/// a generated form together with the boundary it will be encoded with
+#[derive(Debug, Clone, PartialEq)]
+pub struct Form {
+    pub boundary: String,
+    pub fields: FormFields,
+    /// the invalid input the form contains on purpose, when generating invalid values
+    pub violation: Option<Violation>,
+}
+
+impl Form {
+    /// picks a boundary from `rng` so the encoded body is reproducible from the seed
+    pub fn random_boundary(rng: &mut StdRng) -> String {
+        format!("{:016x}{:016x}", rng.random::<u64>(), rng.random::<u64>())
+    }
+
+    /// value for the Content-Type header of the request
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// the raw multipart/form-data body, exactly as it's sent
+    pub fn body(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (name, value) in &self.fields {
+            body.extend_from_slice(format!("--{}\r\n", self.boundary).as_bytes());
+            let disposition = format!("Content-Disposition: form-data; name=\"{}\"", escape_header_value(name));
+            match value {
+                FieldValue::Text(text) => {
+                    body.extend_from_slice(format!("{disposition}\r\n\r\n").as_bytes());
+                    body.extend_from_slice(text.as_bytes());
+                }
+                FieldValue::File { filename, content_type, data } => {
+                    body.extend_from_slice(format!(
+                        "{disposition}; filename=\"{}\"\r\nContent-Type: {content_type}\r\n\r\n",
+                        escape_header_value(filename)
+                    ).as_bytes());
+                    body.extend_from_slice(data);
+                }
+            }
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+        body
+    }
+}
+
+/// percent encodes the characters that would end a quoted header parameter early,
+/// the same way browsers encode field names
+fn escape_header_value(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
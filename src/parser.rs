@@ -1,49 +1,727 @@
 use nom::bytes::take_while1;
-use nom::combinator::value;
-use nom::{Parser, AsChar, character};
+use nom::combinator::{cut, map_res, opt, recognize, value, verify};
+use nom::{Parser, Offset};
 use nom::branch::alt;
 use nom::bytes::complete::{tag, escaped_transform};
-use nom::error::{ParseError, make_error, ErrorKind};
-use nom::multi::separated_list1;
-use nom::sequence::{separated_pair, tuple};
-use nom::{IResult, sequence::delimited};
-use nom::character::complete::{char, anychar, none_of, one_of, digit1};
+use nom::error::{ParseError, ErrorKind, FromExternalError};
+use nom::multi::{many0, many0_count};
+use nom::{IResult, sequence::{delimited, preceded}};
+use nom::character::complete::{alpha1, alphanumeric1, char, digit1, multispace1, not_line_ending};
+use nom::number::complete::recognize_float;
+use std::{collections::{HashMap, HashSet}, fmt::Write, path::PathBuf};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+use crate::{checksum::{CardBrand, IBAN_COUNTRIES, Identifier}, dataset::Dataset, distribution::{self, Histogram}, ids::{NumberFormat, UuidVersion}, locale::{AddressPart, Locale, NamePart}, password::PasswordPolicy, payload::PayloadSet, regex_gen::Pattern, text::{Corpus, TextStyle}, unique::UniqueValues, upload::Uploads};
 
 /// Form Fuzzing Language
 /// functions:
-/// static_str(key,val)
-/// email(key,domains: arr)
+/// static_str(key,val) (or static(key,val)), val can be a ref(name,transform?)
+/// email(key,domains: arr,distribution?)
+/// yes_no(key,probability?)
 /// choose_any(kvps: arr<(k,v)>)
 /// choose_n(n: usize,kvps: arr<(k,v)>)
+/// checkboxes(kvps: arr<(k,v)>,probability?)
+/// weighted(options: arr<(k,v,weight)>)
 /// cellphone(name)
-/// date(name,min,max)
-/// optional(key,val)
-/// string(name,maxlen)
-/// name(name,maxlen)
+/// date(name,min,max,format?)
+/// time(name,min?,max?,format?)
+/// datetime(name,min,max,format?)
+/// optional(key,probability?)
+/// string(name,maxlen,distribution?)
+/// name(name,maxlen,locale?) or name(name,maxlen,distribution?)
+/// first_name(name,locale?), last_name(name,locale?), phone(name,locale?)
+/// address(parts: arr<(part,field)>,locale?) where part is street, city, region, postcode or country
+/// file(name,source,content_type?) where source is path(p), dir(p) or bytes(min,max,distribution?)
+/// regex(name,pattern)
+/// text(name,min_words,max_words,style?,distribution?) where style is lorem or markov(path)
+/// card(name,brand?,invalid?), iban(name,country?,invalid?), isbn(name,invalid?), ean(name,invalid?)
+/// password(name,min,max,policy?,distribution?) where policy is an arr of upper, lower, digits, symbols (each
+/// optionally with a count, e.g. digits(2)) and forbid(chars)
+/// payload(name,set) where set is unicode, long, crlf, boundary, percent or all
+/// counter(name,start,step?), seq(name,format) and uuid(name,version?,fresh?) where version is v4 or v7
+/// int(name,min,max,distribution?)
+/// float(name,min,max,precision,distribution?)
+/// money(name,min,max,currency,distribution?)
+/// distribution is uniform, normal(mean,sd), log_uniform, exponential(mean), fixed(n), zipf(exponent)
+/// or histogram(path). on string, name, email, text, password and bytes it picks the length
+/// one_of(generators), repeat(min,max,generator), maybe(probability,generator), unique(generator)
+/// generator?probability, shorthand for maybe(probability,generator). the probability defaults to 0.5
+/// template(name,"text with {generator(args)} and {ref:name}")
+/// csv(path,columns: arr<(column,field)>,order?) where order is sequential, random or unique
+/// let var = generator
+/// include(path)
+/// fragment name { generators } and use name(prefix?)
 #[derive(Debug, Clone, PartialEq)]
 pub enum Parameter {
     /// generate a random email.
     /// name is the name of the parameter
-    Email {name: String, domains: Vec<String>},
-    YesNo{name: String},
+    Email {name: String, domains: Vec<String>, length: Distribution},
+    /// "Yes" with the given probability, "No" otherwise
+    YesNo{name: String, probability: f64},
     CellPhone {name: String},
-    ChooseAny {options: Vec<(String, String)>},
-    ChooseN{n: usize, kvps: Vec<(String, String)>},
 
-    /// outputs in mm/dd/yyyy format
-    Date {name: String, min: usize, max: usize},
+    /// n different options, in a random order. `choose_any`, `choose_n` and `one_of`
+    Choose {n: usize, options: Vec<Parameter>},
+    
+    /// the generator run min..=max times
+    Repeat {min: usize, max: usize, param: Box<Parameter>},
+    
+    /// the generator run with the given probability, nothing otherwise. `optional` is a maybe
+    Maybe {probability: f64, param: Box<Parameter>},
+    
+    /// the generator, redrawn until it gives a value it hasn't given before in the run
+    Unique {param: Box<Parameter>, seen: UniqueValues},
+
+    /// day in min..=max, formatted with a chrono format string (mm/dd/yyyy by default)
+    Date {name: String, min: DateTimeBound, max: DateTimeBound, format: String},
+    
+    /// time of day in min..=max (HH:MM by default)
+    Time {name: String, min: NaiveTime, max: NaiveTime, format: String},
+    
+    /// date and time in min..=max, to the second (ISO 8601 by default)
+    DateTime {name: String, min: DateTimeBound, max: DateTimeBound, format: String},
 
     /// each checkbox is actually a different param specified by name,value, and then a random subset of them
-    /// is selected
-    CheckBoxes {kvps: Vec<(String, String)>},
+    /// is selected. each one is checked with the given probability
+    CheckBoxes {kvps: Vec<(String, String)>, probability: f64},
+    
+    /// one of the options, picked with a probability proportional to its weight
+    Weighted {options: Vec<(f64, Parameter)>},
+
+    String {name: String, max_len: usize, length: Distribution},
+    
+    Static {name: String, val: Value},
+    
+    /// two random blobs of letters and digits at most max_len long each, or a realistic first and
+    /// last name from the locale. for a name from a locale, `length` picks how long each part may be
+    /// and is max_len unless a distribution is given
+    Name {name: String, max_len: usize, locale: Option<Locale>, length: Distribution},
+    
+    /// first or last name out of the locale's names
+    PersonName {name: String, part: NamePart, locale: Locale},
+    
+    /// one address in the locale, with each mapped part sent as a field
+    Address {parts: Vec<(AddressPart, String)>, locale: Locale},
+    
+    /// E.164 phone number in the locale
+    Phone {name: String, locale: Locale},
+    
+    /// file upload part. content_type defaults to a guess from the file name
+    File {name: String, source: FileSource, content_type: Option<String>},
+    
+    /// string matched by a regular expression
+    Regex {name: String, pattern: Pattern},
+    
+    /// sentences of min..=max words
+    Text {name: String, min: usize, max: usize, style: TextStyle, length: Distribution},
+    
+    /// min..=max characters following the policy
+    Password {name: String, min: usize, max: usize, policy: PasswordPolicy, length: Distribution},
+    
+    /// identifier with check digits, which are wrong on purpose if `invalid`
+    Checksum {name: String, kind: Identifier, invalid: bool},
+    
+    /// adversarial value out of one of the built-in corpora
+    Payload {name: String, set: PayloadSet},
+    
+    /// start + index * step for the request with number `index`, so no two requests of a run get
+    /// the same number. `counter` and `seq`
+    Counter {name: String, start: i64, step: i64, format: NumberFormat},
+    
+    /// random bits come from the form's rng unless `fresh`, which draws new ones on every run
+    Uuid {name: String, version: UuidVersion, fresh: bool},
+    
+    /// whole number in min..=max
+    Int {name: String, min: i64, max: i64, distribution: Distribution},
+    
+    /// decimal number in min..=max printed with `precision` digits after the point
+    Float {name: String, min: f64, max: f64, precision: usize, distribution: Distribution},
+    
+    /// amount in min..=max printed with as many decimals as `currency` uses (2 for USD, 0 for JPY, ..)
+    Money {name: String, min: f64, max: f64, currency: String, distribution: Distribution},
+    
+    /// text put together from literal parts, inline generators and references
+    Template {name: String, parts: Vec<TemplatePart>},
+    
+    /// one row of a csv file per form, with each mapped column sent as a field
+    Csv {dataset: Dataset, columns: Vec<(String, String)>, order: RowOrder},
+    
+    /// `let var = generator`. generates a value once per form that can be referred to with
+    /// `ref(var)`, without sending it as a field itself
+    Let {var: String, param: Box<Parameter>}
+}
 
-    String {name: String, max_len: usize},
+/// value of a field, either written out in the config or taken from elsewhere in the same form
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Literal(String),
+    /// value of a `let` binding or of a field defined earlier in the config
+    Ref {name: String, transform: Transform},
+}
 
-    OptionalString {name: String ,},
+impl From<&str> for Value {
+    fn from(literal: &str) -> Self {
+        Value::Literal(literal.to_string())
+    }
+}
+
+/// piece of a `template(...)`
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplatePart {
+    /// literal text or `{ref:name}`
+    Value(Value),
+    /// `{generator(args)}`, written without a field name. the first text value it produces is used
+    Generator(Parameter),
+}
+
+/// which row of a `csv(...)` dataset a form gets
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RowOrder {
+    /// row number `index` of the request, starting over at the end of the file
+    Sequential,
+    /// any row, rows can repeat
+    Random,
+    /// every row once in a random order, the run stops when they've all been used
+    Unique,
+}
+
+/// change made to a referenced value before it's used
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transform {
+    None,
+    Lower,
+    Upper,
+    /// lowercase ascii letters and digits, with runs of anything else turned into a single `_`
+    Slug,
+}
+
+/// end of a date or datetime range
+#[derive(Debug, Clone, PartialEq)]
+pub enum DateTimeBound {
+    At(NaiveDateTime),
+    /// offset from the moment the config is loaded, e.g. `now-30d`
+    FromNow(TimeDelta),
+}
+
+pub const DEFAULT_DATE_FORMAT: &str = "%m/%d/%Y";
+pub const DEFAULT_TIME_FORMAT: &str = "%H:%M";
+pub const DEFAULT_DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// how numbers, or the lengths of strings, are spread over their min..=max range. samples that
+/// fall outside the range are redrawn a few times and then clamped to it, so a distribution much
+/// wider than the range piles up at min and max
+#[derive(Debug, Clone, PartialEq)]
+pub enum Distribution {
+    Uniform,
+    Normal {mean: f64, sd: f64},
+    /// uniform over the logarithm, so small values are much more common than large ones
+    LogUniform,
+    /// min plus an exponentially distributed offset with the given mean
+    Exponential {mean: f64},
+    /// always the same number, clamped to the range
+    Fixed(f64),
+    /// min is the most common value, then min + 1 and so on, the k-th with a weight of
+    /// 1 / k^exponent
+    Zipf {exponent: f64},
+    /// numbers weighted the way a histogram file says
+    Histogram(Histogram),
+}
+
+/// where the contents of a `file(...)` upload come from
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileSource {
+    /// always the same file
+    Path(Uploads),
+    /// a random file out of a directory
+    Dir(Uploads),
+    /// random bytes, with a size in min_size..=max_size
+    Random {min_size: usize, max_size: usize, distribution: Distribution},
+}
+
+impl Parameter {
+    /// names of the fields this generator can produce, or the variable a `let` binds
+    pub fn field_names(&self) -> Vec<&str> {
+        match self {
+            Parameter::CheckBoxes { kvps, .. } => kvps.iter().map(|(name, _)| name.as_str()).collect(),
+            Parameter::Weighted { options } => options.iter().flat_map(|(_, option)| option.field_names()).collect(),
+            Parameter::Choose { options, .. } => options.iter().flat_map(Parameter::field_names).collect(),
+            Parameter::Repeat { param, .. }
+            | Parameter::Maybe { param, .. }
+            | Parameter::Unique { param, .. } => param.field_names(),
+            Parameter::Let { var, .. } => vec![var],
+            Parameter::Csv { columns, .. } => columns.iter().map(|(_, field)| field.as_str()).collect(),
+            Parameter::Address { parts, .. } => parts.iter().map(|(_, field)| field.as_str()).collect(),
+            Parameter::Email { name, .. }
+            | Parameter::YesNo { name, .. }
+            | Parameter::CellPhone { name }
+            | Parameter::Date { name, .. }
+            | Parameter::Time { name, .. }
+            | Parameter::DateTime { name, .. }
+            | Parameter::String { name, .. }
+            | Parameter::Static { name, .. }
+            | Parameter::Name { name, .. }
+            | Parameter::File { name, .. }
+            | Parameter::Regex { name, .. }
+            | Parameter::Payload { name, .. }
+            | Parameter::Text { name, .. }
+            | Parameter::Password { name, .. }
+            | Parameter::Checksum { name, .. }
+            | Parameter::PersonName { name, .. }
+            | Parameter::Phone { name, .. }
+            | Parameter::Counter { name, .. }
+            | Parameter::Uuid { name, .. }
+            | Parameter::Int { name, .. }
+            | Parameter::Float { name, .. }
+            | Parameter::Money { name, .. }
+            | Parameter::Template { name, .. } => vec![name],
+        }
+    }
+    
+    /// names this generator refers to with `ref(...)`
+    pub fn refs(&self) -> Vec<&str> {
+        match self {
+            Parameter::Static { val: Value::Ref { name, .. }, .. } => vec![name],
+            Parameter::Let { param, .. }
+            | Parameter::Repeat { param, .. }
+            | Parameter::Maybe { param, .. }
+            | Parameter::Unique { param, .. } => param.refs(),
+            Parameter::Choose { options, .. } => options.iter().flat_map(Parameter::refs).collect(),
+            Parameter::Weighted { options } => options.iter().flat_map(|(_, option)| option.refs()).collect(),
+            Parameter::Template { parts, .. } => parts.iter().flat_map(|part| match part {
+                TemplatePart::Value(Value::Ref { name, .. }) => vec![name.as_str()],
+                TemplatePart::Value(Value::Literal(_)) => Vec::new(),
+                TemplatePart::Generator(param) => param.refs(),
+            }).collect(),
+            _ => Vec::new(),
+        }
+    }
+    
+    /// generators this one wraps, including the inline generators of a template
+    pub(crate) fn nested(&self) -> Vec<&Parameter> {
+        match self {
+            Parameter::Let { param, .. }
+            | Parameter::Repeat { param, .. }
+            | Parameter::Maybe { param, .. }
+            | Parameter::Unique { param, .. } => vec![param],
+            Parameter::Choose { options, .. } => options.iter().collect(),
+            Parameter::Weighted { options } => options.iter().map(|(_, option)| option).collect(),
+            Parameter::Template { parts, .. } => parts.iter().filter_map(|part| match part {
+                TemplatePart::Generator(param) => Some(param),
+                TemplatePart::Value(_) => None,
+            }).collect(),
+            _ => Vec::new(),
+        }
+    }
     
-    Static {name: String, val: String},
+    /// whether this generator or one it wraps uploads a file. `unique(...)` only remembers text
+    fn sends_file(&self) -> bool {
+        matches!(self, Parameter::File { .. }) || self.nested().into_iter().any(Parameter::sends_file)
+    }
     
-    Name {name: String, max_len: usize}
+    /// whether a `csv(..., unique)` is somewhere inside this generator. the run limit and the row
+    /// shuffle only look at the top level, and inside e.g. a `repeat` a form would use a row twice
+    fn wraps_unique_csv(&self) -> bool {
+        self.nested().into_iter().any(|param| {
+            matches!(param, Parameter::Csv { order: RowOrder::Unique, .. }) || param.wraps_unique_csv()
+        })
+    }
+    
+    /// puts `prefix` in front of every field name and `let` variable, and of the references to
+    /// names in `local`. used when a fragment is instantiated
+    pub(crate) fn add_prefix(&mut self, prefix: &str, local: &HashSet<String>) {
+        let prefix_ref = |value: &mut Value| {
+            if let Value::Ref { name, .. } = value {
+                if local.contains(name) {
+                    name.insert_str(0, prefix);
+                }
+            }
+        };
+        match self {
+            Parameter::CheckBoxes { kvps, .. } => kvps.iter_mut().for_each(|(name, _)| name.insert_str(0, prefix)),
+            Parameter::Weighted { options } => options.iter_mut().for_each(|(_, option)| option.add_prefix(prefix, local)),
+            Parameter::Choose { options, .. } => options.iter_mut().for_each(|option| option.add_prefix(prefix, local)),
+            Parameter::Repeat { param, .. } | Parameter::Maybe { param, .. } => param.add_prefix(prefix, local),
+            Parameter::Unique { param, seen } => {
+                // every use of a fragment keeps track of its own values
+                *seen = UniqueValues::default();
+                param.add_prefix(prefix, local);
+            }
+            Parameter::Csv { columns, .. } => columns.iter_mut().for_each(|(_, field)| field.insert_str(0, prefix)),
+            Parameter::Address { parts, .. } => parts.iter_mut().for_each(|(_, field)| field.insert_str(0, prefix)),
+            Parameter::Let { var, param } => {
+                var.insert_str(0, prefix);
+                param.add_prefix(prefix, local);
+            }
+            Parameter::Static { name, val } => {
+                name.insert_str(0, prefix);
+                prefix_ref(val);
+            }
+            Parameter::Template { name, parts } => {
+                name.insert_str(0, prefix);
+                for part in parts {
+                    match part {
+                        TemplatePart::Value(value) => prefix_ref(value),
+                        TemplatePart::Generator(param) => param.add_prefix(prefix, local),
+                    }
+                }
+            }
+            Parameter::Email { name, .. }
+            | Parameter::YesNo { name, .. }
+            | Parameter::CellPhone { name }
+            | Parameter::Date { name, .. }
+            | Parameter::Time { name, .. }
+            | Parameter::DateTime { name, .. }
+            | Parameter::String { name, .. }
+            | Parameter::Name { name, .. }
+            | Parameter::File { name, .. }
+            | Parameter::Regex { name, .. }
+            | Parameter::Payload { name, .. }
+            | Parameter::Text { name, .. }
+            | Parameter::Password { name, .. }
+            | Parameter::Checksum { name, .. }
+            | Parameter::PersonName { name, .. }
+            | Parameter::Phone { name, .. }
+            | Parameter::Counter { name, .. }
+            | Parameter::Uuid { name, .. }
+            | Parameter::Int { name, .. }
+            | Parameter::Float { name, .. }
+            | Parameter::Money { name, .. } => name.insert_str(0, prefix),
+        }
+    }
+}
+
+/// one top level entry of a config file
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    /// a generator or `let`
+    Param(Parameter),
+    /// `include("path")`, replaced by the statements of another config file
+    Include(String),
+    /// `fragment name { ... }`, a block of generators that can be used several times
+    Fragment {name: String, body: Vec<Statement>},
+    /// `use name("prefix")`, the generators of a fragment with `prefix` in front of their field names
+    Use {fragment: String, prefix: String},
+}
+
+/// what the statements so far have defined: field and `let` names, which later `ref(...)`s can
+/// name, and fragments, which later `use`s can instantiate
+#[derive(Debug, Default)]
+pub(crate) struct Definitions {
+    names: HashSet<String>,
+    /// where each `let` is defined, for messages
+    lets: HashMap<String, String>,
+    fragments: HashMap<String, Vec<Parameter>>,
+}
+
+impl Definitions {
+    /// adds the generators `statement` stands for to `params`. fails with a description of what
+    /// was expected instead if it refers to something that isn't defined yet, or defines a `let`
+    /// again. `site` is where the statement is, e.g. `a.form:3`
+    pub(crate) fn add(&mut self, statement: Statement, site: &str, params: &mut Vec<Parameter>) -> Result<(), String> {
+        match statement {
+            Statement::Param(param) => self.push(param, site, params),
+            Statement::Use { fragment, prefix } => {
+                for param in self.instantiate(&fragment, &prefix)? {
+                    self.push(param, site, params)?;
+                }
+                Ok(())
+            }
+            Statement::Fragment { name, body } => {
+                if self.fragments.contains_key(&name) {
+                    return Err(format!("fragment name that isn't taken yet, `{name}` is already defined"));
+                }
+                // uses inside the body are expanded right away, so a fragment can't use itself
+                let mut expanded = Vec::new();
+                for statement in body {
+                    match statement {
+                        Statement::Param(param) => expanded.push(param),
+                        Statement::Use { fragment, prefix } => expanded.extend(self.instantiate(&fragment, &prefix)?),
+                        _ => return Err("a generator, `let` or `use` inside a fragment".to_string()),
+                    }
+                }
+                self.fragments.insert(name, expanded);
+                Ok(())
+            }
+            Statement::Include(_) => Err("a generator, `include(...)` only works in config files".to_string()),
+        }
+    }
+    
+    fn push(&mut self, param: Parameter, site: &str, params: &mut Vec<Parameter>) -> Result<(), String> {
+        if param.wraps_unique_csv() {
+            return Err("a csv with `unique` rows on a line of its own, not inside another generator or a `let`".to_string());
+        }
+        if let Some(name) = param.refs().into_iter().find(|name| !self.names.contains(*name)) {
+            return Err(format!("`ref(\"{name}\")` to name a field or `let` defined earlier in the config"));
+        }
+        if let Parameter::Let { var, .. } = &param {
+            if let Some(first) = self.lets.get(var) {
+                return Err(format!("a `let` name that isn't taken yet, `{var}` is already defined at {first}"));
+            }
+            self.lets.insert(var.clone(), site.to_string());
+        }
+        self.names.extend(param.field_names().into_iter().map(str::to_string));
+        params.push(param);
+        Ok(())
+    }
+    
+    fn instantiate(&self, fragment: &str, prefix: &str) -> Result<Vec<Parameter>, String> {
+        let body = self.fragments.get(fragment)
+            .ok_or_else(|| format!("`use {fragment}` to name a fragment defined earlier"))?;
+        // a ref is to a name in the fragment if the fragment defines it before the ref
+        let mut local = HashSet::new();
+        Ok(body.iter().map(|param| {
+            let mut prefixed = param.clone();
+            prefixed.add_prefix(prefix, &local);
+            local.extend(param.field_names().into_iter().map(str::to_string));
+            prefixed
+        }).collect())
+    }
+}
+
+/// nom error that remembers where parsing stopped and, once a generator has been
+/// recognised, what the parser expected to find there
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError<'a> {
+    /// remaining input at the point of failure
+    pub input: &'a str,
+    /// human readable description of what was expected, e.g. "`,` after field name in `date(...)`"
+    pub expected: Option<String>,
+}
+
+impl<'a> SyntaxError<'a> {
+    /// byte offset of the failure within `source`, which must be the string that was parsed
+    pub fn offset_in(&self, source: &str) -> usize {
+        source.offset(self.input)
+    }
+}
+
+impl<'a> ParseError<&'a str> for SyntaxError<'a> {
+    fn from_error_kind(input: &'a str, _kind: ErrorKind) -> Self {
+        SyntaxError { input, expected: None }
+    }
+
+    fn append(_input: &'a str, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn or(self, other: Self) -> Self {
+        // keep whichever branch made it further, it's the one the user most likely meant
+        if other.input.len() < self.input.len()
+            || (other.input.len() == self.input.len() && self.expected.is_none()) {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+impl<'a, E> FromExternalError<&'a str, E> for SyntaxError<'a> {
+    fn from_external_error(input: &'a str, kind: ErrorKind, _e: E) -> Self {
+        Self::from_error_kind(input, kind)
+    }
+}
+
+type PResult<'a, O> = IResult<&'a str, O, SyntaxError<'a>>;
+
+/// names of every generator `parse_generator` understands, used for the "unknown generator" message
+pub const GENERATORS: &[&str] = &[
+    "static", "static_str", "email", "yes_no", "choose_n", "cellphone", "choose_any", "checkboxes",
+    "date", "time", "datetime", "string", "optional", "name", "file", "regex", "int", "float", "money",
+    "template", "csv", "one_of", "repeat", "maybe", "weighted", "payload", "unique",
+    "counter", "seq", "uuid", "first_name", "last_name", "address", "phone",
+    "text", "password", "card", "iban", "isbn", "ean",
+];
+
+/// generators whose first argument isn't a field name, they're written the same way in templates
+const UNNAMED: &[&str] = &["choose_any", "choose_n", "checkboxes", "csv", "one_of", "repeat", "maybe", "weighted", "unique", "address"];
+
+/// attaches a description of what was expected to any failure of `parser` that doesn't have one yet
+fn expect<'a, P>(what: String, mut parser: P) -> impl Parser<&'a str, Output = P::Output, Error = SyntaxError<'a>>
+where
+    P: Parser<&'a str, Error = SyntaxError<'a>>
+{
+    move |input: &'a str| parser.parse(input).map_err(|err| err.map(|mut e| {
+        e.expected.get_or_insert_with(|| what.clone());
+        e
+    }))
+}
+
+/// like `expect`, but when none of the alternatives inside got far enough to fail for good, points
+/// at the start with `what` instead of at whatever the furthest of them said it expected
+fn expect_one_of<'a, P>(what: String, mut parser: P) -> impl Parser<&'a str, Output = P::Output, Error = SyntaxError<'a>>
+where
+    P: Parser<&'a str, Error = SyntaxError<'a>>
+{
+    move |input: &'a str| parser.parse(input).map_err(|err| match err {
+        nom::Err::Error(_) => nom::Err::Error(SyntaxError { input, expected: Some(what.clone()) }),
+        other => other,
+    })
+}
+
+/// turns a recoverable error into a fatal one so `alt` doesn't try the other branches
+fn fatal(err: nom::Err<SyntaxError<'_>>) -> nom::Err<SyntaxError<'_>> {
+    match err {
+        nom::Err::Error(e) => nom::Err::Failure(e),
+        other => other,
+    }
+}
+
+/// `#` or `//` comment running to the end of the line
+fn comment(input: &str) -> PResult<'_, &str> {
+    preceded(alt((tag("#"), tag("//"))), not_line_ending).parse(input)
+}
+
+/// skips any mix of whitespace, newlines and comments
+fn ws(input: &str) -> PResult<'_, ()> {
+    value((), many0_count(alt((multispace1, comment)))).parse(input)
+}
+
+/// `func(` args `)`. once `func(` has matched any failure is fatal, so the error points at the
+/// broken argument instead of backtracking into the other generators
+fn call<'a, P>(func: &'static str, args: P) -> impl Parser<&'a str, Output = P::Output, Error = SyntaxError<'a>>
+where
+    P: Parser<&'a str, Error = SyntaxError<'a>>
+{
+    delimited(
+        (keyword(func), ws, char('(')),
+        cut(args),
+        cut(preceded(ws, expect(format!("`)` to close `{func}(...)`"), char(')'))))
+    )
+}
+
+fn comma<'a>(after: &'static str, func: &'static str) -> impl Parser<&'a str, Output = char, Error = SyntaxError<'a>> {
+    preceded(ws, expect(format!("`,` after {after} in `{func}(...)`"), char(',')))
+}
+
+fn field_name<'a>(func: &'static str) -> impl Parser<&'a str, Output = String, Error = SyntaxError<'a>> {
+    preceded(ws, expect(format!("field name (a quoted string) in `{func}(...)`"), parse_string))
+}
+
+fn string_arg<'a>(what: &'static str, func: &'static str) -> impl Parser<&'a str, Output = String, Error = SyntaxError<'a>> {
+    preceded(ws, expect(format!("{what} (a quoted string) in `{func}(...)`"), parse_string))
+}
+
+/// literal string or `ref("name", transform?)`
+fn value_arg<'a>(what: &'static str, func: &'static str) -> impl Parser<&'a str, Output = Value, Error = SyntaxError<'a>> {
+    preceded(ws, expect(
+        format!("{what} (a quoted string or `ref(\"name\")`) in `{func}(...)`"),
+        alt((parse_string.map(Value::Literal), parse_ref))
+    ))
+}
+
+fn parse_ref(input: &str) -> PResult<'_, Value> {
+    let transform = preceded(ws, expect("transform: `lower`, `upper` or `slug` in `ref(...)`".to_string(), alt((
+        value(Transform::Lower, keyword("lower")),
+        value(Transform::Upper, keyword("upper")),
+        value(Transform::Slug, keyword("slug")),
+    ))));
+    call("ref", (
+        string_arg("field or let name", "ref"),
+        opt(preceded((ws, char(',')), cut(transform)))
+    )).map(|(name, transform)| Value::Ref { name, transform: transform.unwrap_or(Transform::None) }).parse(input)
+}
+
+fn number<'a>(what: &'static str, func: &'static str) -> impl Parser<&'a str, Output = usize, Error = SyntaxError<'a>> {
+    preceded(ws, expect(format!("{what} (a whole number) in `{func}(...)`"), map_res(digit1, str::parse::<usize>)))
+}
+
+/// longest max length of a string-like generator, 4 MiB. lengths are uniform by default, so
+/// anything much larger would build strings that can't be held in memory
+const MAX_LENGTH: usize = 4 << 20;
+
+/// max length of a string-like generator. its shortest value is one character, so a max of 0
+/// would leave nothing to generate
+fn max_length<'a>(func: &'static str) -> impl Parser<&'a str, Output = usize, Error = SyntaxError<'a>> {
+    expect(
+        format!("max length from 1 to {MAX_LENGTH} in `{func}(...)`"),
+        verify(number("max length", func), |max_len| (1..=MAX_LENGTH).contains(max_len))
+    )
+}
+
+fn integer<'a>(what: &'static str, func: &'static str) -> impl Parser<&'a str, Output = i64, Error = SyntaxError<'a>> {
+    preceded(ws, expect(
+        format!("{what} (an integer) in `{func}(...)`"),
+        map_res(recognize((opt(char('-')), digit1)), str::parse::<i64>)
+    ))
+}
+
+fn decimal<'a>(what: &'static str, func: &'static str) -> impl Parser<&'a str, Output = f64, Error = SyntaxError<'a>> {
+    preceded(ws, expect(
+        format!("{what} (a number) in `{func}(...)`"),
+        verify(map_res(recognize_float, str::parse::<f64>), |n: &f64| n.is_finite())
+    ))
+}
+
+const DISTRIBUTIONS: &str = "`uniform`, `normal(mean,sd)`, `log_uniform`, `exponential(mean)`, `fixed(n)`, `zipf(exponent)` or `histogram(\"file\")`";
+
+/// one of `DISTRIBUTIONS`
+fn parse_distribution(input: &str) -> PResult<'_, Distribution> {
+    preceded(ws, expect(format!("distribution: {DISTRIBUTIONS}"), distribution)).parse(input)
+}
+
+fn distribution(input: &str) -> PResult<'_, Distribution> {
+    alt((
+        value(Distribution::Uniform, keyword("uniform")),
+        value(Distribution::LogUniform, keyword("log_uniform")),
+        call("normal", (
+            decimal("mean", "normal"),
+            preceded(comma("mean", "normal"), expect(
+                "standard deviation no smaller than 0 in `normal(...)`".to_string(),
+                verify(decimal("standard deviation", "normal"), |sd| *sd >= 0.0)
+            ))
+        )).map(|(mean, sd)| Distribution::Normal { mean, sd }),
+        call("exponential", expect(
+            "mean larger than 0 in `exponential(...)`".to_string(),
+            verify(decimal("mean", "exponential"), |mean| *mean > 0.0)
+        )).map(|mean| Distribution::Exponential { mean }),
+        call("fixed", decimal("value", "fixed")).map(Distribution::Fixed),
+        call("zipf", expect(
+            "exponent larger than 0 in `zipf(...)`".to_string(),
+            verify(decimal("exponent", "zipf"), |exponent| *exponent > 0.0)
+        )).map(|exponent| Distribution::Zipf { exponent }),
+        call("histogram", string_arg("histogram file", "histogram"))
+            .map(|path| Distribution::Histogram(Histogram::new(PathBuf::from(path)))),
+    )).parse(input)
+}
+
+/// optional `, distribution` at the end of a numeric or length-bearing generator, uniform if left
+/// out
+fn trailing_distribution(input: &str) -> PResult<'_, Distribution> {
+    opt(preceded((ws, char(',')), cut(parse_distribution)))
+        .map(|distribution| distribution.unwrap_or(Distribution::Uniform))
+        .parse(input)
+}
+
+/// number that can be the min or max of a range
+trait Bound: PartialOrd {
+    /// whether `max - min` can be worked out without overflowing
+    fn span_is_finite(_min: &Self, _max: &Self) -> bool {
+        true
+    }
+}
+
+impl Bound for usize {}
+
+impl Bound for i64 {}
+
+impl Bound for f64 {
+    fn span_is_finite(min: &Self, max: &Self) -> bool {
+        (max - min).is_finite()
+    }
+}
+
+/// `min, max` with min <= max and a finite `max - min`
+fn bounds<'a, O, P>(func: &'static str, mut bound: impl FnMut(&'static str) -> P) -> impl Parser<&'a str, Output = (O, O), Error = SyntaxError<'a>>
+where
+    O: Bound,
+    P: Parser<&'a str, Output = O, Error = SyntaxError<'a>>
+{
+    let min = bound("min");
+    let max = preceded(comma("min", func), bound("max"));
+    let ordered = expect(format!("max no smaller than min in `{func}(...)`"), verify((min, max), |(min, max)| min <= max));
+    expect(
+        format!("min and max close enough that `max - min` is a finite number in `{func}(...)`"),
+        verify(ordered, |(min, max)| O::span_is_finite(min, max))
+    )
 }
 
 /// esc characters:
@@ -51,132 +729,940 @@ pub enum Parameter {
 /// - \) -> )
 /// - \n -> newline
 /// - \" -> "
-fn parse_string(input: &str) -> IResult<&str, String> {
-    delimited(
-        char('\"'),
-        escaped_transform(
+fn parse_string(input: &str) -> PResult<'_, String> {
+    let (rest, _) = char('\"').parse(input)?;
+    cut((
+        // escaped_transform fails on empty input, so `""` is handled by the opt
+        opt(escaped_transform(
             take_while1(|c| c != '\\' && c != '\"'),
             '\\',
-            alt((
-                value("(", char('(')),
-                value(")", char(')')),
-                value("\n", char('n')),
-                value("\"", char('"')),
-                value("\\", char('\\')),
-            ))
-        ),
-        char('\"')
-    ).parse(input)
+            escape_sequence
+        )).map(Option::unwrap_or_default),
+        expect("closing `\"` of string literal".to_string(), char('\"'))
+    )).map(|(s, _)| s).parse(rest)
 }
 
-fn parse_kvp(input: &str) -> IResult<&str, (String, String)> {
-    delimited(
-        char('('),
-        separated_pair(parse_string, char(','), parse_string),
-        char(')')
-    ).parse(input)
+/// what follows the `\\` of an escape sequence
+fn escape_sequence(input: &str) -> PResult<'_, &str> {
+    expect("a valid escape sequence (`\\(`, `\\)`, `\\n`, `\\\"` or `\\\\`)".to_string(), alt((
+        value("(", char('(')),
+        value(")", char(')')),
+        value("\n", char('n')),
+        value("\"", char('"')),
+        value("\\", char('\\')),
+    ))).parse(input)
+}
+
+/// string literal where backslashes are kept as they are, apart from `\"`. used for regexes so
+/// `\d` doesn't have to be written `\\d`
+fn parse_raw_string(input: &str) -> PResult<'_, String> {
+    let (rest, _) = char('\"').parse(input)?;
+    let mut value = String::new();
+    let mut chars = rest.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((&rest[i + 1..], value)),
+            '\\' if rest[i + 1..].starts_with('"') => {
+                value.push('"');
+                chars.next();
+            }
+            c => value.push(c),
+        }
+    }
+    Err(nom::Err::Failure(SyntaxError { input: &rest[rest.len()..], expected: Some("closing `\"` of string literal".to_string()) }))
+}
+
+fn parse_kvp<'a>(func: &'static str) -> impl Parser<&'a str, Output = (String, String), Error = SyntaxError<'a>> {
+    preceded(
+        preceded(ws, expect(format!("`(\"name\",\"value\")` pair in `{func}(...)`"), char('('))),
+        cut((
+            string_arg("pair name", func),
+            preceded(comma("pair name", func), string_arg("pair value", func)),
+            preceded(ws, expect(format!("`)` to close pair in `{func}(...)`"), char(')')))
+        ))
+    ).map(|(k, v, _)| (k, v))
 }
 
-fn parse_arr_custom<'a, F, E>(matcher: F) -> impl Parser<&'a str, Output = Vec<<F as Parser<&'a str>>::Output>, Error = E>
+/// `[` one or more comma separated `matcher`s, optionally followed by a trailing comma, `]`
+fn parse_arr_custom<'a, F>(what: &'static str, func: &'static str, mut matcher: F) -> impl Parser<&'a str, Output = Vec<<F as Parser<&'a str>>::Output>, Error = SyntaxError<'a>>
 where
-    E: ParseError<&'a str>,
-    F: Parser<&'a str, Error = E>
+    F: Parser<&'a str, Error = SyntaxError<'a>>
 {
-    delimited(char('['), separated_list1(char(','), matcher), char(']'))
+    let mut open = preceded(ws, expect(format!("`[` to open {what} in `{func}(...)`"), char('[')));
+    let mut close = expect(format!("`,` or `]` to close {what} in `{func}(...)`"), char(']'));
+    move |input: &'a str| {
+        let (input, _) = open.parse(input)?;
+        let (mut input, first) = matcher.parse(input).map_err(fatal)?;
+        let mut items = vec![first];
+        loop {
+            let (rest, _) = ws(input)?;
+            if let Ok((rest, _)) = char::<_, SyntaxError>(',').parse(rest) {
+                let (rest, _) = ws(rest)?;
+                if let Ok((rest, _)) = char::<_, SyntaxError>(']').parse(rest) {
+                    return Ok((rest, items));
+                }
+                let (rest, item) = matcher.parse(rest).map_err(fatal)?;
+                items.push(item);
+                input = rest;
+            } else {
+                let (rest, _) = close.parse(rest)?;
+                return Ok((rest, items));
+            }
+        }
+    }
 }
 
-fn parse_name(input: &str) -> IResult<&str, Parameter> {
-    let inner = (parse_string, char(','), take_while1(AsChar::is_dec_digit));
-    let (input, (name, _, max_len)) = delimited(tag("name("), 
-        inner
-        , tag(")")).parse(input)?;
-    return IResult::Ok((input, Parameter::Name{name: name.to_string(), max_len: str::parse(max_len).unwrap()}));
+fn parse_name(input: &str) -> PResult<'_, Parameter> {
+    let codes: Vec<&str> = Locale::ALL.iter().map(|locale| locale.code()).collect();
+    let locale_or_length = preceded(ws, expect_one_of(
+        format!("locale ({}) or length distribution ({DISTRIBUTIONS}) in `name(...)`", codes.join(", ")),
+        alt((
+            distribution.map(|length| (None, Some(length))),
+            (
+                identifier.map_opt(Locale::parse),
+                opt(preceded((ws, char(',')), cut(parse_distribution)))
+            ).map(|(locale, length)| (Some(locale), length)),
+        ))
+    ));
+    call("name", (
+        field_name("name"),
+        preceded(comma("field name", "name"), max_length("name")),
+        opt(preceded((ws, char(',')), cut(locale_or_length)))
+    )).map(|(name, max_len, options)| {
+        let (locale, length) = options.unwrap_or((None, None));
+        let length = length.unwrap_or(match locale {
+            Some(_) => Distribution::Fixed(max_len as f64),
+            None => Distribution::Uniform,
+        });
+        Parameter::Name { name, max_len, locale, length }
+    }).parse(input)
 }
 
-fn parse_static(input: &str) -> IResult<&str, Parameter> {
-    let kvp = delimited(tag("static("), 
-        separated_pair(parse_string, char(','), parse_string)
-        , tag(")")).parse(input)?;
-    return IResult::Ok((kvp.0.into(), Parameter::Static{name:kvp.1.0.to_owned(), val:kvp.1.1.to_owned()}));
+/// `en_US` style locale or `US` style country code
+fn locale<'a>(func: &'static str) -> impl Parser<&'a str, Output = Locale, Error = SyntaxError<'a>> {
+    let codes: Vec<&str> = Locale::ALL.iter().map(|locale| locale.code()).collect();
+    preceded(ws, expect(
+        format!("locale, one of: {} (or just the country code) in `{func}(...)`", codes.join(", ")),
+        identifier.map_opt(Locale::parse)
+    ))
 }
 
+/// optional `, locale` at the end of a person generator, `en_US` by default
+fn trailing_locale<'a>(func: &'static str) -> impl Parser<&'a str, Output = Locale, Error = SyntaxError<'a>> {
+    opt(preceded((ws, char(',')), cut(locale(func)))).map(Option::unwrap_or_default)
+}
 
-fn parse_email(input: &str) -> IResult<&str, Parameter> {
-    let result = delimited(tag("email("), 
-        separated_pair(parse_string, char(','), parse_arr_custom(parse_string)),
-        tag(")")).parse(input)?;
-    return IResult::Ok((result.0, Parameter::Email{
-        name:result.1.0.to_string(), 
-        domains:result.1.1}))
+fn parse_person_name(input: &str) -> PResult<'_, Parameter> {
+    let person_name = |func, part| call(func, (field_name(func), trailing_locale(func)))
+        .map(move |(name, locale)| Parameter::PersonName { name, part, locale });
+    alt((person_name("first_name", NamePart::First), person_name("last_name", NamePart::Last))).parse(input)
 }
 
-fn parse_choose_any(input: &str) -> IResult<&str, Parameter> {
-    let result = delimited(
-        tag("choose_any("),
-        parse_arr_custom(parse_kvp),
-        tag(")")
-    ).parse(input)?;
-    Ok((result.0, Parameter::ChooseAny { options: result.1 }))
+fn parse_address(input: &str) -> PResult<'_, Parameter> {
+    let names: Vec<&str> = AddressPart::NAMES.iter().map(|(name, _)| *name).collect();
+    let pair = preceded(
+        preceded(ws, expect("`(\"part\",\"field\")` pair in `address(...)`".to_string(), char('('))),
+        cut((
+            preceded(ws, expect(
+                format!("address part, one of: {} in `address(...)`", names.join(", ")),
+                parse_string.map_opt(|part| AddressPart::parse(&part))
+            )),
+            preceded(comma("address part", "address"), field_name("address")),
+            preceded(ws, expect("`)` to close pair in `address(...)`".to_string(), char(')')))
+        ))
+    ).map(|(part, field, _)| (part, field));
+    call("address", (
+        parse_arr_custom("the list of (\"part\",\"field\") pairs", "address", pair),
+        trailing_locale("address")
+    )).map(|(parts, locale)| Parameter::Address { parts, locale }).parse(input)
 }
 
-fn parse_choose_n(input: &str) -> IResult<&str, Parameter> {
-    let func = separated_pair(
-        take_while1(|c| AsChar::is_dec_digit(c)), 
-        tag(","),
-        parse_arr_custom(parse_kvp)
-    );
-    let result = delimited(
-        tag("choose_n("),
-        func,
-        tag(")")
-    ).parse(input)?;
-    Ok((result.0, Parameter::ChooseN { n: result.1.0.parse::<usize>().unwrap(), kvps: result.1.1 }))
-}
-
-fn parse_cellphone(input: &str) -> IResult<&str, Parameter> {
-    let result = delimited(tag("cellphone("), parse_string, tag(")")).parse(input)?;
-    return IResult::Ok((result.0, Parameter::CellPhone{name: result.1}));
-}
-
-fn parse_date(input: &str) -> IResult<&str, Parameter> {
-    let name_dates = (parse_string, char(','), take_while1(AsChar::is_dec_digit), char(','), take_while1(AsChar::is_dec_digit));
-    let (input, (name,_,d1,_,d2)) = delimited(
-        tag("date("),
-        name_dates,
-        tag(")")
-    ).parse(input)?;
-    return IResult::Ok((input, Parameter::Date{
-        name: name.to_string(), 
-        min: str::parse::<usize>(d1).unwrap(), 
-        max: str::parse::<usize>(d2).unwrap()
+fn parse_text(input: &str) -> PResult<'_, Parameter> {
+    let style = alt((
+        value(TextStyle::Lorem, keyword("lorem")),
+        call("markov", string_arg("corpus file", "markov")).map(|path| TextStyle::Markov(Corpus::new(PathBuf::from(path)))),
+    ));
+    let style_or_length = preceded(ws, expect_one_of(
+        format!("text style (`lorem` or `markov(\"corpus file\")`) or length distribution ({DISTRIBUTIONS}) in `text(...)`"),
+        alt((
+            (style, trailing_distribution),
+            distribution.map(|length| (TextStyle::Lorem, length)),
+        ))
+    ));
+    call("text", (
+        field_name("text"),
+        preceded(comma("field name", "text"), bounds("text", |what| number(what, "text"))),
+        opt(preceded((ws, char(',')), cut(style_or_length)))
+    )).map(|(name, (min, max), options)| {
+        let (style, length) = options.unwrap_or((TextStyle::Lorem, Distribution::Uniform));
+        Parameter::Text { name, min, max, style, length }
+    }).parse(input)
+}
+
+/// one rule of a password policy
+#[derive(Debug, Clone)]
+enum PolicyRule {
+    /// least number of characters out of the class
+    Class(fn(&mut PasswordPolicy) -> &mut usize, usize),
+    Forbid(String),
+}
+
+fn parse_password(input: &str) -> PResult<'_, Parameter> {
+    let class = |name: &'static str, field: fn(&mut PasswordPolicy) -> &mut usize| alt((
+        call(name, number("count", "password")).map(move |count| PolicyRule::Class(field, count)),
+        keyword(name).map(move |_| PolicyRule::Class(field, 1)),
+    ));
+    let rule = preceded(ws, expect(
+        "policy rule: `upper`, `lower`, `digits`, `symbols` or `forbid(\"chars\")` in `password(...)`".to_string(),
+        alt((
+            class("upper", |policy| &mut policy.upper),
+            class("lower", |policy| &mut policy.lower),
+            class("digits", |policy| &mut policy.digits),
+            class("symbols", |policy| &mut policy.symbols),
+            call("forbid", string_arg("forbidden characters", "forbid")).map(PolicyRule::Forbid),
+        ))
+    ));
+    let policy = parse_arr_custom("the password policy", "password", rule).map(|rules| {
+        let mut policy = PasswordPolicy::default();
+        for rule in rules {
+            match rule {
+                PolicyRule::Class(field, count) => *field(&mut policy) = count,
+                PolicyRule::Forbid(chars) => policy.forbidden.push_str(&chars),
+            }
+        }
+        policy
+    });
+    let policy_or_length = preceded(ws, expect_one_of(
+        format!("password policy (an arr) or length distribution ({DISTRIBUTIONS}) in `password(...)`"),
+        alt((
+            distribution.map(|length| (PasswordPolicy::default(), length)),
+            (policy, trailing_distribution),
+        ))
+    ));
+    call("password", expect(
+        "policy that a password no longer than max can follow, with characters left to use, in `password(...)`".to_string(),
+        verify((
+            field_name("password"),
+            preceded(comma("field name", "password"), bounds("password", |what| number(what, "password"))),
+            opt(preceded((ws, char(',')), cut(policy_or_length)))
+        ), |(_, (_, max), options)| options.as_ref().is_none_or(|(policy, _)| policy.is_satisfiable(*max)))
+    )).map(|(name, (min, max), options)| {
+        let (policy, length) = options.unwrap_or((PasswordPolicy::default(), Distribution::Uniform));
+        Parameter::Password { name, min, max, policy, length }
+    }).parse(input)
+}
+
+/// optional `, invalid` at the end of an identifier generator. `what` is everything that can
+/// come after the comma
+fn invalid_flag<'a>(what: String) -> impl Parser<&'a str, Output = bool, Error = SyntaxError<'a>> {
+    opt(preceded((ws, char(',')), cut(preceded(ws, expect(what, keyword("invalid")))))).map(|flag| flag.is_some())
+}
+
+fn parse_checksum(input: &str) -> PResult<'_, Parameter> {
+    let brands: Vec<&str> = CardBrand::NAMES.iter().map(|(name, _)| *name).collect();
+    let brand = preceded((ws, char(','), ws), identifier.map_opt(|word| {
+        CardBrand::NAMES.iter().find(|(name, _)| *name == word).map(|(_, brand)| *brand)
     }));
+    let card = call("card", (
+        field_name("card"),
+        opt(brand),
+        invalid_flag(format!("card brand ({}) or `invalid` in `card(...)`", brands.join(", ")))
+    )).map(|(name, brand, invalid)| Parameter::Checksum { name, kind: Identifier::Card(brand), invalid });
+    
+    let countries: Vec<&str> = IBAN_COUNTRIES.iter().map(|(code, _)| *code).collect();
+    let country = preceded((ws, char(','), ws), identifier.map_opt(|word| {
+        IBAN_COUNTRIES.iter().find(|(code, _)| *code == word).map(|(code, _)| *code)
+    }));
+    let iban = call("iban", (
+        field_name("iban"),
+        opt(country),
+        invalid_flag(format!("country ({}) or `invalid` in `iban(...)`", countries.join(", ")))
+    )).map(|(name, country, invalid)| Parameter::Checksum { name, kind: Identifier::Iban(country.unwrap_or("DE")), invalid });
+    
+    let plain = |func: &'static str, kind| call(func, (field_name(func), invalid_flag(format!("`invalid` in `{func}(...)`"))))
+        .map(move |(name, invalid)| Parameter::Checksum { name, kind, invalid });
+    alt((card, iban, plain("isbn", Identifier::Isbn13), plain("ean", Identifier::Ean13))).parse(input)
 }
 
+fn parse_phone(input: &str) -> PResult<'_, Parameter> {
+    call("phone", (field_name("phone"), trailing_locale("phone")))
+        .map(|(name, locale)| Parameter::Phone { name, locale })
+        .parse(input)
+}
+
+fn parse_static(input: &str) -> PResult<'_, Parameter> {
+    let args = |func| (
+        field_name(func),
+        preceded(comma("field name", func), value_arg("value", func))
+    );
+    alt((call("static_str", args("static_str")), call("static", args("static"))))
+        .map(|(name, val)| Parameter::Static { name, val })
+        .parse(input)
+}
+
+fn parse_yes_no(input: &str) -> PResult<'_, Parameter> {
+    call("yes_no", (field_name("yes_no"), trailing_probability("yes_no")))
+        .map(|(name, probability)| Parameter::YesNo { name, probability })
+        .parse(input)
+}
+
+fn parse_checkboxes(input: &str) -> PResult<'_, Parameter> {
+    call("checkboxes", (
+        parse_arr_custom("the list of pairs", "checkboxes", parse_kvp("checkboxes")),
+        trailing_probability("checkboxes")
+    )).map(|(kvps, probability)| Parameter::CheckBoxes { kvps, probability }).parse(input)
+}
+
+/// `("name","value",weight)` in `weighted(...)`
+fn parse_weighted_option(input: &str) -> PResult<'_, (f64, Parameter)> {
+    let weight = expect(
+        "weight (a number no smaller than 0) in `weighted(...)`".to_string(),
+        verify(decimal("weight", "weighted"), |weight| *weight >= 0.0)
+    );
+    preceded(
+        preceded(ws, expect("`(\"name\",\"value\",weight)` in `weighted(...)`".to_string(), char('('))),
+        cut((
+            string_arg("name", "weighted"),
+            preceded(comma("name", "weighted"), string_arg("value", "weighted")),
+            preceded(comma("value", "weighted"), weight),
+            preceded(ws, expect("`)` to close option in `weighted(...)`".to_string(), char(')')))
+        ))
+    ).map(|(name, val, weight, _)| (weight, Parameter::Static { name, val: Value::Literal(val) })).parse(input)
+}
+
+fn parse_weighted(input: &str) -> PResult<'_, Parameter> {
+    let options = expect(
+        "at least one weight larger than 0 in `weighted(...)`".to_string(),
+        verify(
+            parse_arr_custom("the list of options", "weighted", parse_weighted_option),
+            |options: &Vec<(f64, Parameter)>| options.iter().any(|(weight, _)| *weight > 0.0)
+        )
+    );
+    call("weighted", expect(
+        "weights with a finite sum in `weighted(...)`".to_string(),
+        verify(options, |options: &Vec<(f64, Parameter)>| options.iter().map(|(weight, _)| weight).sum::<f64>().is_finite())
+    )).map(|options| Parameter::Weighted { options }).parse(input)
+}
+
+/// sent with an empty value with the given probability, half of the time by default
+fn parse_optional(input: &str) -> PResult<'_, Parameter> {
+    call("optional", (field_name("optional"), trailing_probability("optional")))
+        .map(|(name, probability)| Parameter::Maybe {
+            probability,
+            param: Box::new(Parameter::Static { name, val: Value::Literal(String::new()) }),
+        })
+        .parse(input)
+}
+
+/// `static` generators for a list of name, value pairs
+fn statics(kvps: Vec<(String, String)>) -> Vec<Parameter> {
+    kvps.into_iter().map(|(name, val)| Parameter::Static { name, val: Value::Literal(val) }).collect()
+}
+
+fn parse_email(input: &str) -> PResult<'_, Parameter> {
+    let domain = expect(
+        "domain that isn't empty in `email(...)`".to_string(),
+        verify(string_arg("domain", "email"), |domain: &str| !domain.is_empty())
+    );
+    call("email", (
+        field_name("email"),
+        preceded(comma("field name", "email"), parse_arr_custom("the list of domains", "email", domain)),
+        trailing_distribution
+    )).map(|(name, domains, length)| Parameter::Email { name, domains, length }).parse(input)
+}
+
+fn parse_choose_any(input: &str) -> PResult<'_, Parameter> {
+    call("choose_any", parse_arr_custom("the list of pairs", "choose_any", parse_kvp("choose_any")))
+        .map(|kvps| Parameter::Choose { n: 1, options: statics(kvps) })
+        .parse(input)
+}
+
+fn parse_choose_n(input: &str) -> PResult<'_, Parameter> {
+    call("choose_n", (
+        number("n", "choose_n"),
+        preceded(comma("n", "choose_n"), parse_arr_custom("the list of pairs", "choose_n", parse_kvp("choose_n")))
+    )).map(|(n, kvps)| Parameter::Choose { n, options: statics(kvps) }).parse(input)
+}
+
+/// a generator passed as an argument to another one
+fn generator_arg<'a>(func: &'static str) -> impl Parser<&'a str, Output = Parameter, Error = SyntaxError<'a>> {
+    preceded(ws, expect(format!("a generator in `{func}(...)`"), parse_generator))
+}
+
+fn parse_one_of(input: &str) -> PResult<'_, Parameter> {
+    call("one_of", (
+        generator_arg("one_of"),
+        many0(preceded((ws, char(',')), cut(generator_arg("one_of"))))
+    )).map(|(first, rest)| Parameter::Choose { n: 1, options: [vec![first], rest].concat() }).parse(input)
+}
 
-fn parse_string_entry(input: &str) -> IResult<&str, Parameter> {
-    let inner = (parse_string, char(','), take_while1(AsChar::is_dec_digit));
-    let (input, (name, _, max_len)) = delimited(tag("string("), 
-        inner
-        , tag(")")).parse(input)?;
-    return IResult::Ok((input, Parameter::String{name: name.to_string(), max_len: str::parse(max_len).unwrap()}));
+fn parse_repeat(input: &str) -> PResult<'_, Parameter> {
+    call("repeat", (
+        bounds("repeat", |what| number(what, "repeat")),
+        preceded(comma("max", "repeat"), generator_arg("repeat"))
+    )).map(|((min, max), param)| Parameter::Repeat { min, max, param: Box::new(param) }).parse(input)
 }
 
-pub fn parse_line(input: &str) -> IResult<&str, Parameter> {
+/// probability (a number from 0 to 1)
+fn probability<'a>(func: &'static str) -> impl Parser<&'a str, Output = f64, Error = SyntaxError<'a>> {
+    expect(
+        format!("probability (a number from 0 to 1) in `{func}(...)`"),
+        verify(decimal("probability", func), |p| (0.0..=1.0).contains(p))
+    )
+}
+
+/// optional `, probability` at the end of a generator, 0.5 if left out
+fn trailing_probability<'a>(func: &'static str) -> impl Parser<&'a str, Output = f64, Error = SyntaxError<'a>> {
+    opt(preceded((ws, char(',')), cut(probability(func))))
+        .map(|probability| probability.unwrap_or(0.5))
+}
+
+fn parse_maybe(input: &str) -> PResult<'_, Parameter> {
+    call("maybe", (
+        probability("maybe"),
+        preceded(comma("probability", "maybe"), generator_arg("maybe"))
+    )).map(|(probability, param)| Parameter::Maybe { probability, param: Box::new(param) }).parse(input)
+}
+
+fn parse_counter(input: &str) -> PResult<'_, Parameter> {
+    call("counter", (
+        field_name("counter"),
+        preceded(comma("field name", "counter"), integer("start", "counter")),
+        opt(preceded((ws, char(',')), cut(expect(
+            "step other than 0 in `counter(...)`".to_string(),
+            verify(integer("step", "counter"), |step| *step != 0)
+        ))))
+    )).map(|(name, start, step)| Parameter::Counter {
+        name,
+        start,
+        step: step.unwrap_or(1),
+        format: NumberFormat::default(),
+    }).parse(input)
+}
+
+fn parse_seq(input: &str) -> PResult<'_, Parameter> {
+    let format = preceded(ws, expect(
+        "format with one `{}` or `{:06}` style placeholder, e.g. \"INV-{:06}\", in `seq(...)`".to_string(),
+        parse_string.map_opt(|format| NumberFormat::parse(&format))
+    ));
+    call("seq", (
+        field_name("seq"),
+        preceded(comma("field name", "seq"), format)
+    )).map(|(name, format)| Parameter::Counter { name, start: 1, step: 1, format })
+        .parse(input)
+}
+
+fn parse_uuid(input: &str) -> PResult<'_, Parameter> {
+    let version = preceded((ws, char(','), ws), alt((
+        value(UuidVersion::V4, keyword("v4")),
+        value(UuidVersion::V7, keyword("v7")),
+    )));
+    let fresh = opt(preceded((ws, char(',')), cut(preceded(ws, expect(
+        "uuid version: `v4` or `v7`, or `fresh` in `uuid(...)`".to_string(),
+        keyword("fresh")
+    )))));
+    call("uuid", (field_name("uuid"), opt(version), fresh))
+        .map(|(name, version, fresh)| Parameter::Uuid { name, version: version.unwrap_or(UuidVersion::V4), fresh: fresh.is_some() })
+        .parse(input)
+}
+
+fn parse_unique(input: &str) -> PResult<'_, Parameter> {
+    call("unique", expect(
+        "a generator that uploads no files in `unique(...)`".to_string(),
+        verify(generator_arg("unique"), |param| !param.sends_file())
+    )).map(|param| Parameter::Unique { param: Box::new(param), seen: UniqueValues::default() })
+        .parse(input)
+}
+
+fn parse_cellphone(input: &str) -> PResult<'_, Parameter> {
+    call("cellphone", field_name("cellphone"))
+        .map(|name| Parameter::CellPhone { name })
+        .parse(input)
+}
+
+/// `now`, optionally followed by an offset such as `-30d` or `+2h` (units s, m, h, d, w)
+fn parse_from_now(input: &str) -> PResult<'_, TimeDelta> {
+    let offset = (
+        alt((value(1, char('+')), value(-1, char('-')))),
+        cut(expect("offset such as `30d` after `now+`/`now-` (units s, m, h, d or w)".to_string(), (
+            map_res(digit1, str::parse::<i64>),
+            alt((
+                value(1, char('s')),
+                value(60, char('m')),
+                value(3600, char('h')),
+                value(86400, char('d')),
+                value(604800, char('w')),
+            ))
+        )))
+    );
+    preceded(keyword("now"), opt(offset)).map_opt(|offset| match offset {
+        None => Some(TimeDelta::zero()),
+        Some((sign, (amount, unit))) => amount.checked_mul(unit)
+            .and_then(|secs| TimeDelta::try_seconds(sign * secs)),
+    }).parse(input)
+}
+
+/// a year (`1990`), a quoted date or datetime (`"1990-01-15"`, `"1990-01-15T08:30:00"`) or a time
+/// relative to now (`now-30d`). a bare year means the start of the year for `min` and its
+/// last second for `max`
+fn datetime_bound<'a>(what: &'static str, func: &'static str) -> impl Parser<&'a str, Output = DateTimeBound, Error = SyntaxError<'a>> {
+    let is_max = what == "max";
+    let year = map_res(digit1, str::parse::<i32>).map_opt(move |year| match is_max {
+        false => NaiveDate::from_ymd_opt(year, 1, 1)?.and_hms_opt(0, 0, 0),
+        true => NaiveDate::from_ymd_opt(year, 12, 31)?.and_hms_opt(23, 59, 59),
+    });
+    let quoted = parse_string.map_opt(move |text| {
+        NaiveDateTime::parse_from_str(&text, "%Y-%m-%dT%H:%M:%S")
+            .or_else(|_| NaiveDateTime::parse_from_str(&text, "%Y-%m-%d %H:%M:%S"))
+            .ok()
+            .or_else(|| {
+                let day = NaiveDate::parse_from_str(&text, "%Y-%m-%d").ok()?;
+                match is_max {
+                    false => day.and_hms_opt(0, 0, 0),
+                    true => day.and_hms_opt(23, 59, 59),
+                }
+            })
+    });
+    preceded(ws, expect(
+        format!("{what} (a year, a \"yyyy-mm-dd\" date or `now-30d` style offset) in `{func}(...)`"),
+        alt((
+            parse_from_now.map(DateTimeBound::FromNow),
+            year.map(DateTimeBound::At),
+            quoted.map(DateTimeBound::At),
+        ))
+    ))
+}
+
+/// `min, max` of a date or datetime, with min <= max where both are fixed or both are offsets
+/// from now. a mix of the two is only known once the config is loaded
+fn datetime_bounds<'a>(func: &'static str) -> impl Parser<&'a str, Output = (DateTimeBound, DateTimeBound), Error = SyntaxError<'a>> {
+    let in_order = |(min, max): &(DateTimeBound, DateTimeBound)| match (min, max) {
+        (DateTimeBound::At(min), DateTimeBound::At(max)) => min <= max,
+        (DateTimeBound::FromNow(min), DateTimeBound::FromNow(max)) => min <= max,
+        _ => true,
+    };
+    let min = datetime_bound("min", func);
+    let max = preceded(comma("min", func), datetime_bound("max", func));
+    expect(format!("max no smaller than min in `{func}(...)`"), verify((min, max), in_order))
+}
+
+fn time_bound<'a>(what: &'static str) -> impl Parser<&'a str, Output = NaiveTime, Error = SyntaxError<'a>> {
+    preceded(ws, expect(
+        format!("{what} (a \"HH:MM\" or \"HH:MM:SS\" time) in `time(...)`"),
+        parse_string.map_opt(|text| {
+            NaiveTime::parse_from_str(&text, "%H:%M:%S")
+                .or_else(|_| NaiveTime::parse_from_str(&text, "%H:%M"))
+                .ok()
+        })
+    ))
+}
+
+/// optional `, "format"` at the end of a date or time generator. the format is tried out on a
+/// sample date, so one chrono can't render is a syntax error pointing at the string
+fn trailing_format<'a>(func: &'static str, default: &'static str) -> impl Parser<&'a str, Output = String, Error = SyntaxError<'a>> {
+    let format = expect(
+        format!("chrono format string without time zone fields, e.g. \"%d.%m.%Y\", in `{func}(...)`"),
+        verify(parse_string, |format: &String| {
+            write!(String::new(), "{}", NaiveDateTime::default().format(format)).is_ok()
+        })
+    );
+    opt(preceded((ws, char(',')), cut(preceded(ws, format))))
+        .map(move |format| format.unwrap_or_else(|| default.to_string()))
+}
+
+fn parse_date(input: &str) -> PResult<'_, Parameter> {
+    call("date", (
+        field_name("date"),
+        preceded(comma("field name", "date"), datetime_bounds("date")),
+        trailing_format("date", DEFAULT_DATE_FORMAT)
+    )).map(|(name, (min, max), format)| Parameter::Date { name, min, max, format }).parse(input)
+}
+
+fn parse_time(input: &str) -> PResult<'_, Parameter> {
+    // the bounds are optional, a quoted string that isn't a time is the format instead
+    let bounds = opt((
+        preceded((ws, char(',')), time_bound("min")),
+        cut(preceded(comma("min", "time"), time_bound("max")))
+    ));
+    call("time", (
+        field_name("time"),
+        bounds,
+        trailing_format("time", DEFAULT_TIME_FORMAT)
+    )).map(|(name, bounds, format)| {
+        let (min, max) = bounds.unwrap_or((NaiveTime::MIN, NaiveTime::from_hms_opt(23, 59, 59).unwrap()));
+        Parameter::Time { name, min, max, format }
+    }).parse(input)
+}
+
+fn parse_datetime(input: &str) -> PResult<'_, Parameter> {
+    call("datetime", (
+        field_name("datetime"),
+        preceded(comma("field name", "datetime"), datetime_bounds("datetime")),
+        trailing_format("datetime", DEFAULT_DATETIME_FORMAT)
+    )).map(|(name, (min, max), format)| Parameter::DateTime { name, min, max, format }).parse(input)
+}
+
+fn parse_string_entry(input: &str) -> PResult<'_, Parameter> {
+    call("string", (
+        field_name("string"),
+        preceded(comma("field name", "string"), max_length("string")),
+        trailing_distribution
+    )).map(|(name, max_len, length)| Parameter::String { name, max_len, length }).parse(input)
+}
+
+fn parse_file_source(input: &str) -> PResult<'_, FileSource> {
+    let random = call("bytes", (
+        expect(
+            "max size no smaller than min size in `bytes(...)`".to_string(),
+            verify((
+                number("min size", "bytes"),
+                preceded(comma("min size", "bytes"), number("max size", "bytes"))
+            ), |(min, max)| min <= max)
+        ),
+        trailing_distribution
+    ));
+    preceded(ws, expect("file source: `path(\"file\")`, `dir(\"directory\")` or `bytes(min,max)` in `file(...)`".to_string(), alt((
+        call("path", string_arg("path", "path")).map(|p| FileSource::Path(Uploads::new(PathBuf::from(p)))),
+        call("dir", string_arg("directory", "dir")).map(|p| FileSource::Dir(Uploads::new(PathBuf::from(p)))),
+        random.map(|((min_size, max_size), distribution)| FileSource::Random { min_size, max_size, distribution }),
+    )))).parse(input)
+}
+
+fn parse_file(input: &str) -> PResult<'_, Parameter> {
+    call("file", (
+        field_name("file"),
+        preceded(comma("field name", "file"), parse_file_source),
+        opt(preceded((ws, char(',')), cut(string_arg("content type", "file"))))
+    )).map(|(name, source, content_type)| Parameter::File { name, source, content_type }).parse(input)
+}
+
+fn parse_regex(input: &str) -> PResult<'_, Parameter> {
+    let pattern = |input| {
+        let (rest, source) = preceded(ws, expect("pattern (a quoted string) in `regex(...)`".to_string(), parse_raw_string)).parse(input)?;
+        match Pattern::parse(&source) {
+            Ok(pattern) => Ok((rest, pattern)),
+            Err(reason) => Err(nom::Err::Failure(SyntaxError {
+                input: input.trim_start(),
+                expected: Some(format!("a valid regular expression in `regex(...)` ({reason})")),
+            })),
+        }
+    };
+    call("regex", (
+        field_name("regex"),
+        preceded(comma("field name", "regex"), pattern)
+    )).map(|(name, pattern)| Parameter::Regex { name, pattern }).parse(input)
+}
+
+fn parse_int(input: &str) -> PResult<'_, Parameter> {
+    call("int", (
+        field_name("int"),
+        preceded(comma("field name", "int"), bounds("int", |what| integer(what, "int"))),
+        trailing_distribution
+    )).map(|(name, (min, max), distribution)| Parameter::Int { name, min, max, distribution }).parse(input)
+}
+
+/// most digits after the point a `float(...)` can have, about as many as an f64 holds
+const MAX_PRECISION: usize = 15;
+
+fn parse_float(input: &str) -> PResult<'_, Parameter> {
+    let precision = expect(
+        format!("precision (a whole number up to {MAX_PRECISION}) in `float(...)`"),
+        verify(number("precision", "float"), |precision| *precision <= MAX_PRECISION)
+    );
+    let range = expect(
+        "min and max with a number that has `precision` decimals between them in `float(...)`".to_string(),
+        verify(
+            (bounds("float", |what| decimal(what, "float")), preceded(comma("max", "float"), precision)),
+            |((min, max), precision)| distribution::holds_decimal(*min, *max, *precision)
+        )
+    );
+    call("float", (
+        field_name("float"),
+        preceded(comma("field name", "float"), range),
+        trailing_distribution
+    )).map(|(name, ((min, max), precision), distribution)| Parameter::Float { name, min, max, precision, distribution }).parse(input)
+}
+
+fn parse_money(input: &str) -> PResult<'_, Parameter> {
+    let range = expect(
+        "min and max with an amount in whole units of the currency between them in `money(...)`".to_string(),
+        verify(
+            (bounds("money", |what| decimal(what, "money")), preceded(comma("max", "money"), string_arg("currency code", "money"))),
+            |((min, max), currency)| distribution::holds_decimal(*min, *max, distribution::currency_decimals(currency))
+        )
+    );
+    call("money", (
+        field_name("money"),
+        preceded(comma("field name", "money"), range),
+        trailing_distribution
+    )).map(|(name, ((min, max), currency), distribution)| Parameter::Money { name, min, max, currency, distribution }).parse(input)
+}
+
+/// generator call inside a template, written without its field name: `int(1,999)`. it's parsed
+/// as the same call with an empty field name put in front of the arguments
+fn inline_generator(input: &str) -> PResult<'_, Parameter> {
+    let (args, (func, _, _)) = (identifier, ws, char('(')).parse(input)?;
+    if UNNAMED.contains(&func) {
+        return parse_generator(input);
+    }
+    let open = input.offset(args);
+    let (after_ws, _) = ws(args)?;
+    let inserted = if after_ws.starts_with(')') { "\"\"" } else { "\"\"," };
+    let named = format!("{}{inserted}{}", &input[..open], args);
+    
+    // map positions in `named` back onto `input`
+    let original = |at: &str| {
+        let offset = named.offset(at);
+        if offset <= open { offset } else { offset.saturating_sub(inserted.len()).max(open) }
+    };
+    match parse_generator(&named) {
+        Ok((rest, param)) => Ok((&input[original(rest)..], param)),
+        Err(err) => Err(err.map(|e| SyntaxError { input: &input[original(e.input)..], expected: e.expected })),
+    }
+}
+
+/// what goes between the braces of a template: `ref:name` or an inline generator
+fn template_part(input: &str) -> PResult<'_, TemplatePart> {
+    let reference = preceded(tag("ref:"), cut(expect(
+        "field or let name after `ref:` in template".to_string(),
+        take_while1(|c| c != '}' && c != '"')
+    ))).map(|name: &str| TemplatePart::Value(Value::Ref { name: name.trim().to_string(), transform: Transform::None }));
+    preceded(ws, expect(
+        "`ref:name` or a generator call without field name such as `int(1,999)` in template".to_string(),
+        alt((reference, inline_generator.map(TemplatePart::Generator)))
+    )).parse(input)
+}
+
+/// quoted template text. `{...}` holds a `template_part`, `{{` and `}}` are literal braces and
+/// backslash escapes work as in other strings
+fn parse_template_text(input: &str) -> PResult<'_, Vec<TemplatePart>> {
+    let (mut rest, _) = char('"').parse(input)?;
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    loop {
+        if let Some(after) = rest.strip_prefix("{{") {
+            text.push('{');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("}}") {
+            text.push('}');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('{') {
+            if !text.is_empty() {
+                parts.push(TemplatePart::Value(Value::Literal(std::mem::take(&mut text))));
+            }
+            let close = preceded(ws, expect("`}` to close `{` in template".to_string(), char('}')));
+            let (after, part) = cut((template_part, close)).map(|(part, _)| part).parse(after)?;
+            parts.push(part);
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('\\') {
+            let (after, escaped) = cut(escape_sequence).parse(after)?;
+            text.push_str(escaped);
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('"') {
+            if !text.is_empty() {
+                parts.push(TemplatePart::Value(Value::Literal(text)));
+            }
+            return Ok((after, parts));
+        } else if let Some(c) = rest.chars().next() {
+            text.push(c);
+            rest = &rest[c.len_utf8()..];
+        } else {
+            return Err(nom::Err::Failure(SyntaxError { input: rest, expected: Some("closing `\"` of template".to_string()) }));
+        }
+    }
+}
+
+fn parse_template(input: &str) -> PResult<'_, Parameter> {
+    call("template", (
+        field_name("template"),
+        preceded(comma("field name", "template"), preceded(ws, expect(
+            "template text (a quoted string) in `template(...)`".to_string(),
+            parse_template_text
+        )))
+    )).map(|(name, parts)| Parameter::Template { name, parts }).parse(input)
+}
+
+fn parse_csv(input: &str) -> PResult<'_, Parameter> {
+    let order = preceded(ws, expect("row order: `sequential`, `random` or `unique` in `csv(...)`".to_string(), alt((
+        value(RowOrder::Sequential, keyword("sequential")),
+        value(RowOrder::Random, keyword("random")),
+        value(RowOrder::Unique, keyword("unique")),
+    ))));
+    call("csv", (
+        string_arg("path", "csv"),
+        preceded(comma("path", "csv"), parse_arr_custom("the list of (\"column\",\"field\") pairs", "csv", parse_kvp("csv"))),
+        opt(preceded((ws, char(',')), cut(order)))
+    )).map(|(path, columns, order)| Parameter::Csv {
+        dataset: Dataset::new(PathBuf::from(path)),
+        columns,
+        order: order.unwrap_or(RowOrder::Sequential),
+    }).parse(input)
+}
+
+fn parse_payload(input: &str) -> PResult<'_, Parameter> {
+    let names: Vec<&str> = PayloadSet::NAMES.iter().map(|(name, _)| *name).collect();
+    let set = preceded(ws, expect(format!("payload set, one of: {} in `payload(...)`", names.join(", ")), identifier.map_opt(|word| {
+        PayloadSet::NAMES.iter().find(|(name, _)| *name == word).map(|(_, set)| *set)
+    })));
+    call("payload", (
+        field_name("payload"),
+        preceded(comma("field name", "payload"), set)
+    )).map(|(name, set)| Parameter::Payload { name, set }).parse(input)
+}
+
+/// a generator, optionally followed by `?` and the probability it's sent with. a bare `?` means
+/// half of the time
+pub fn parse_generator(input: &str) -> PResult<'_, Parameter> {
+    let presence = preceded((ws, char('?')), cut(expect(
+        "presence probability (a number from 0 to 1) after `?`".to_string(),
+        verify(
+            opt(preceded(ws, map_res(recognize_float, str::parse::<f64>))),
+            |probability: &Option<f64>| probability.is_none_or(|p| (0.0..=1.0).contains(&p))
+        )
+    )));
+    (parse_generator_call, opt(presence)).map(|(param, presence)| match presence {
+        Some(probability) => Parameter::Maybe { probability: probability.unwrap_or(0.5), param: Box::new(param) },
+        None => param,
+    }).parse(input)
+}
+
+fn parse_generator_call(input: &str) -> PResult<'_, Parameter> {
+    expect(format!("a generator, one of: {}", GENERATORS.join(", ")), alt((
+        parse_static,
+        parse_email,
+        parse_choose_n,
+        parse_cellphone,
+        parse_choose_any,
+        parse_date,
+        parse_time,
+        parse_datetime,
+        parse_string_entry,
+        parse_name,
+        parse_yes_no,
+        parse_checkboxes,
+        parse_optional,
+        parse_file,
+        parse_regex,
+        parse_int,
+        parse_float,
+        parse_money,
+        parse_template,
+        parse_csv,
+        // alt takes at most 21 parsers, so the combinators are grouped
+        alt((
+            parse_one_of, parse_repeat, parse_maybe, parse_weighted, parse_payload, parse_unique,
+            parse_counter, parse_seq, parse_uuid, parse_person_name, parse_address, parse_phone,
+            parse_text, parse_password, parse_checksum,
+        )),
+    ))).parse(input)
+}
+
+/// `word` as a whole identifier, so `uniformly` isn't read as `uniform` with `ly` left over
+fn keyword<'a>(word: &'static str) -> impl Parser<&'a str, Output = &'a str, Error = SyntaxError<'a>> {
+    verify(identifier, move |found: &str| found == word)
+}
+
+/// letter or `_`, then letters, digits and `_`
+fn identifier(input: &str) -> PResult<'_, &str> {
+    recognize((
+        alt((alpha1, tag("_"))),
+        many0_count(alt((alphanumeric1, tag("_"))))
+    )).parse(input)
+}
+
+/// `let var = generator`
+fn parse_let(input: &str) -> PResult<'_, Parameter> {
+    preceded(
+        (tag("let"), multispace1),
+        cut((
+            expect("variable name after `let`".to_string(), identifier),
+            preceded(ws, expect("`=` after variable name in `let`".to_string(), char('='))),
+            preceded(ws, parse_generator)
+        ))
+    ).map(|(var, _, param)| Parameter::Let { var: var.to_string(), param: Box::new(param) }).parse(input)
+}
+
+/// `use name` or `use name("prefix")`
+fn parse_use(input: &str) -> PResult<'_, Statement> {
+    let prefix = preceded(
+        (ws, char('(')),
+        cut((
+            string_arg("field name prefix", "use"),
+            preceded(ws, expect("`)` to close `use name(...)`".to_string(), char(')')))
+        ))
+    ).map(|(prefix, _)| prefix);
+    preceded(
+        (tag("use"), multispace1),
+        cut((expect("fragment name after `use`".to_string(), identifier), opt(prefix)))
+    ).map(|(fragment, prefix)| Statement::Use { fragment: fragment.to_string(), prefix: prefix.unwrap_or_default() }).parse(input)
+}
+
+/// `fragment name { generators, lets and uses }`
+fn parse_fragment(input: &str) -> PResult<'_, Statement> {
+    let (rest, _) = (tag("fragment"), multispace1).parse(input)?;
+    let (rest, name) = cut(expect("fragment name after `fragment`".to_string(), identifier)).parse(rest)?;
+    let (mut rest, _) = cut(preceded(ws, expect(format!("`{{` to open fragment `{name}`"), char('{')))).parse(rest)?;
+    let mut body = Vec::new();
+    loop {
+        (rest, _) = ws(rest)?;
+        if let Some(after) = rest.strip_prefix('}') {
+            return Ok((after, Statement::Fragment { name: name.to_string(), body }));
+        }
+        let statement = alt((parse_use, parse_let.map(Statement::Param), parse_generator.map(Statement::Param)));
+        let (after, statement) = expect(format!("`}}` to close fragment `{name}`"), statement).parse(rest).map_err(fatal)?;
+        body.push(statement);
+        rest = after;
+    }
+}
+
+/// a generator, `let`, `include(...)`, `fragment` or `use`
+pub fn parse_statement(input: &str) -> PResult<'_, Statement> {
     alt((
-        parse_static, 
-        parse_email, 
-        parse_choose_n, 
-        parse_cellphone, 
-        parse_choose_any, 
-        parse_date, 
-        parse_string_entry, 
-        parse_name
+        call("include", string_arg("path", "include")).map(Statement::Include),
+        parse_fragment,
+        parse_use,
+        parse_let.map(Statement::Param),
+        parse_generator.map(Statement::Param),
     )).parse(input)
 }
 
+/// splits a config file into statements, each with the input it starts at. statements may be
+/// split across lines and separated by any amount of whitespace and comments
+pub fn parse_statements(input: &str) -> Result<Vec<(&str, Statement)>, SyntaxError<'_>> {
+    let into_syntax_error = |err| match err {
+        nom::Err::Error(e) | nom::Err::Failure(e) => e,
+        nom::Err::Incomplete(_) => SyntaxError { input, expected: None },
+    };
+    let mut statements = Vec::new();
+    let (mut rest, _) = ws(input).map_err(into_syntax_error)?;
+    while !rest.is_empty() {
+        let (after, statement) = parse_statement(rest).map_err(into_syntax_error)?;
+        statements.push((rest, statement));
+        (rest, _) = ws(after).map_err(into_syntax_error)?;
+    }
+    Ok(statements)
+}
+
+/// parses a config that doesn't include other files. every `ref(...)` has to name a field or
+/// `let` that comes before it, so references can be filled in while a form is generated front
+/// to back. nothing is loaded: files aren't read and `now` relative dates aren't pinned, which
+/// `Parameter::get_params_from_config` does
+pub fn parse_config(input: &str) -> Result<Vec<Parameter>, SyntaxError<'_>> {
+    let mut params = Vec::new();
+    let mut definitions = Definitions::default();
+    for (at, statement) in parse_statements(input)? {
+        let line = input[..input.offset(at)].matches('\n').count() + 1;
+        definitions.add(statement, &format!("line {line}"), &mut params)
+            .map_err(|expected| SyntaxError { input: at, expected: Some(expected) })?;
+    }
+    Ok(params)
+}
+
 #[cfg(test)]
 mod tests {
+    use std::path::Path;
     use crate::parser::parse_date;
+    use crate::ConfigError;
     use super::*;
     
     #[test]
@@ -185,13 +1671,14 @@ mod tests {
         assert_eq!(parse_string("\"hello\""), Ok(("", "hello".to_string())));
         assert_eq!(parse_string("\"esca\\\"ped\\(chars\\)\""), Ok(("", "esca\"ped(chars)".to_string())));
         assert_eq!(parse_string("\"newline\\n\""), Ok(("", "newline\n".to_string())));
+        assert_eq!(parse_string("\"\""), Ok(("", String::new())));
     }
     
     #[test]
     fn test_parse_static() {
         assert_eq!(
             parse_static("static(\"name\",\"value\")"),
-            Ok(("", Parameter::Static { name: "name".to_string(), val: "value".to_string() }))
+            Ok(("", Parameter::Static { name: "name".to_string(), val: "value".into() }))
         );
     }
     
@@ -199,8 +1686,11 @@ mod tests {
     fn test_parse_email() {
         assert_eq!(
             parse_email("email(\"user\",[\"gmail.com\",\"yahoo.com\"])"),
-            Ok(("", Parameter::Email { name: "user".to_string(), domains: vec!["gmail.com".to_string(), "yahoo.com".to_string()] }))
+            Ok(("", Parameter::Email { name: "user".to_string(), domains: vec!["gmail.com".to_string(), "yahoo.com".to_string()], length: Distribution::Uniform }))
         );
+        let err = parse_config("email(\"e\",[\"x.com\", \"\"])").unwrap_err();
+        assert_eq!(err.input, "\"\"])");
+        assert_eq!(err.expected.unwrap(), "domain that isn't empty in `email(...)`");
     }
     
     #[test]
@@ -208,10 +1698,10 @@ mod tests {
         // Each key-value pair is parsed as a tuple ("key","value")
         assert_eq!(
             parse_choose_any("choose_any([(\"key1\",\"val1\"),(\"key2\",\"val2\")])"),
-            Ok(("", Parameter::ChooseAny { options: vec![
+            Ok(("", Parameter::Choose { n: 1, options: statics(vec![
                 ("key1".to_string(), "val1".to_string()),
                 ("key2".to_string(), "val2".to_string())
-            ] }))
+            ]) }))
         );
     }
     
@@ -220,12 +1710,12 @@ mod tests {
         // First argument is the number, then a list of key-value pairs.
         assert_eq!(
             parse_choose_n("choose_n(2,[(\"key1\",\"val1\"),(\"key2\",\"val2\")])"),
-            Ok(("", Parameter::ChooseN {
+            Ok(("", Parameter::Choose {
                 n: 2,
-                kvps: vec![
+                options: statics(vec![
                     ("key1".to_string(), "val1".to_string()),
                     ("key2".to_string(), "val2".to_string())
-                ]
+                ])
             }))
         );
     }
@@ -238,11 +1728,89 @@ mod tests {
         );
     }
     
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32, sec: u32) -> DateTimeBound {
+        DateTimeBound::At(NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(h, min, sec).unwrap())
+    }
+    
     #[test]
     fn test_parse_date() {
         assert_eq!(
             parse_date("date(\"name\",11,44)"),
-            Ok(("", Parameter::Date { name: "name".to_string(), min: 11, max: 44 }))
+            Ok(("", Parameter::Date {
+                name: "name".to_string(),
+                min: at(11, 1, 1, 0, 0, 0),
+                max: at(44, 12, 31, 23, 59, 59),
+                format: DEFAULT_DATE_FORMAT.to_string()
+            }))
+        );
+        assert_eq!(
+            parse_date("date(\"dob\", \"1990-01-15\", now-30d, \"%d.%m.%Y\")"),
+            Ok(("", Parameter::Date {
+                name: "dob".to_string(),
+                min: at(1990, 1, 15, 0, 0, 0),
+                max: DateTimeBound::FromNow(TimeDelta::days(-30)),
+                format: "%d.%m.%Y".to_string()
+            }))
+        );
+        assert_eq!(
+            parse_config("date(\"d\", \"1990-02-30\", 2000)").unwrap_err().expected.unwrap(),
+            "min (a year, a \"yyyy-mm-dd\" date or `now-30d` style offset) in `date(...)`"
+        );
+        assert!(parse_config("date(\"d\", 1990, 2000, \"%Y %z\")").is_err());
+        assert_eq!(
+            parse_config("date(\"d\", 2000, 1990)").unwrap_err().expected.unwrap(),
+            "max no smaller than min in `date(...)`"
+        );
+        assert!(parse_config("datetime(\"d\", now, now-1d)").is_err());
+        assert!(parse_config("date(\"d\", 1990, 1990)").is_ok());
+    }
+    
+    #[test]
+    fn test_parse_time_and_datetime() {
+        assert_eq!(
+            parse_time("time(\"t\")"),
+            Ok(("", Parameter::Time {
+                name: "t".to_string(),
+                min: NaiveTime::MIN,
+                max: NaiveTime::from_hms_opt(23, 59, 59).unwrap(),
+                format: DEFAULT_TIME_FORMAT.to_string()
+            }))
+        );
+        assert_eq!(
+            parse_time("time(\"t\", \"%I:%M %p\")"),
+            Ok(("", Parameter::Time {
+                name: "t".to_string(),
+                min: NaiveTime::MIN,
+                max: NaiveTime::from_hms_opt(23, 59, 59).unwrap(),
+                format: "%I:%M %p".to_string()
+            }))
+        );
+        assert_eq!(
+            parse_time("time(\"t\",\"09:00\",\"17:30:15\")"),
+            Ok(("", Parameter::Time {
+                name: "t".to_string(),
+                min: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                max: NaiveTime::from_hms_opt(17, 30, 15).unwrap(),
+                format: DEFAULT_TIME_FORMAT.to_string()
+            }))
+        );
+        assert_eq!(
+            parse_datetime("datetime(\"ts\", now-2w, now+1h)"),
+            Ok(("", Parameter::DateTime {
+                name: "ts".to_string(),
+                min: DateTimeBound::FromNow(TimeDelta::weeks(-2)),
+                max: DateTimeBound::FromNow(TimeDelta::hours(1)),
+                format: DEFAULT_DATETIME_FORMAT.to_string()
+            }))
+        );
+        assert_eq!(
+            parse_datetime("datetime(\"ts\",\"2024-01-01 08:00:00\",now)"),
+            Ok(("", Parameter::DateTime {
+                name: "ts".to_string(),
+                min: at(2024, 1, 1, 8, 0, 0),
+                max: DateTimeBound::FromNow(TimeDelta::zero()),
+                format: DEFAULT_DATETIME_FORMAT.to_string()
+            }))
         );
     }
     
@@ -250,7 +1818,594 @@ mod tests {
     fn test_parse_string_entry() {
         assert_eq!(
             parse_string_entry("string(\"name\",11)"),
-            Ok(("", Parameter::String { name: "name".to_string(), max_len:11 }))
+            Ok(("", Parameter::String { name: "name".to_string(), max_len:11, length: Distribution::Uniform }))
+        );
+    }
+    
+    #[test]
+    fn test_error_expectation() {
+        let err = parse_config("date(\"dob\" 1990,2000)").unwrap_err();
+        assert_eq!(err.input, "1990,2000)");
+        assert_eq!(err.expected.as_deref(), Some("`,` after field name in `date(...)`"));
+        
+        let err = parse_config("string(\"name\",)").unwrap_err();
+        assert_eq!(err.input, ")");
+        assert_eq!(err.expected.as_deref(), Some("max length (a whole number) in `string(...)`"));
+        
+        let err = parse_config("cellphone(\"phone\"").unwrap_err();
+        assert_eq!(err.expected.as_deref(), Some("`)` to close `cellphone(...)`"));
+        
+        // a keyword with more letters after it is a different word, not the keyword and junk
+        let err = parse_config("int(\"q\",1,9,uniformly)").unwrap_err();
+        assert_eq!(err.input, "uniformly)");
+        assert_eq!(err.expected.unwrap(), format!("distribution: {DISTRIBUTIONS}"));
+        let err = parse_config("email(\"e\",[\"x.com\"])\nstatic(\"c\",ref(\"e\",lowercase))").unwrap_err();
+        assert_eq!(err.input, "lowercase))");
+        assert_eq!(err.expected.unwrap(), "transform: `lower`, `upper` or `slug` in `ref(...)`");
+        let err = parse_config("password(\"pw\",8,12,[lowercase])").unwrap_err();
+        assert_eq!(err.input, "lowercase])");
+        assert!(err.expected.unwrap().starts_with("policy rule:"));
+    }
+    
+    #[test]
+    fn test_error_unknown_generator_and_trailing_input() {
+        let err = parse_config("telephone(\"p\")").unwrap_err();
+        assert_eq!(err.input, "telephone(\"p\")");
+        assert!(err.expected.unwrap().starts_with("a generator, one of: static"));
+        
+        let err = parse_config("static(\"a\",\"b\") junk").unwrap_err();
+        assert_eq!(err.input, "junk");
+    }
+    
+    #[test]
+    fn test_config_error_location() {
+        let source = "static(\"a\",\"b\")\n\tdate(\"dob\" 1990,2000)\n";
+        let err = parse_config(source).unwrap_err();
+        let err = ConfigError::syntax(Path::new("a.form"), source, err.offset_in(source), err.expected.unwrap());
+        match &err {
+            ConfigError::Syntax { line, column, snippet, .. } => {
+                assert_eq!((*line, *column), (2, 13));
+                assert_eq!(snippet, "\tdate(\"dob\" 1990,2000)");
+            }
+            other => panic!("unexpected error {other:?}"),
+        }
+        assert!(err.to_string().ends_with("2 | \tdate(\"dob\" 1990,2000)\n  | \t           ^"));
+    }
+    
+    #[test]
+    fn test_whitespace_and_comments() {
+        let config = r#"
+            # contact details
+            static( "field name" , "static value" ) // end of line comment
+            email("user", [
+                "gmail.com",
+                "yahoo.com", # trailing comma
+            ])
+
+            choose_n(1, [("radio", "Yes"), ("radio", "No"),])
+        "#;
+        assert_eq!(parse_config(config), Ok(vec![
+            Parameter::Static { name: "field name".to_string(), val: "static value".into() },
+            Parameter::Email { name: "user".to_string(), domains: vec!["gmail.com".to_string(), "yahoo.com".to_string()], length: Distribution::Uniform },
+            Parameter::Choose { n: 1, options: statics(vec![
+                ("radio".to_string(), "Yes".to_string()),
+                ("radio".to_string(), "No".to_string())
+            ]) },
+        ]));
+        assert_eq!(parse_config("  \n# nothing here\n"), Ok(vec![]));
+    }
+    
+    #[test]
+    fn test_parse_new_variants() {
+        assert_eq!(
+            parse_static("static_str(\"name\",\"value\")"),
+            Ok(("", Parameter::Static { name: "name".to_string(), val: "value".into() }))
+        );
+        assert_eq!(parse_yes_no("yes_no(\"agree\")"), Ok(("", Parameter::YesNo { name: "agree".to_string(), probability: 0.5 })));
+        assert_eq!(
+            parse_checkboxes("checkboxes([(\"box\",\"a\"),(\"box\",\"b\")])"),
+            Ok(("", Parameter::CheckBoxes { kvps: vec![
+                ("box".to_string(), "a".to_string()),
+                ("box".to_string(), "b".to_string())
+            ], probability: 0.5 }))
+        );
+        assert_eq!(parse_optional("optional(\"note\")"), Ok(("", Parameter::Maybe {
+            probability: 0.5,
+            param: Box::new(Parameter::Static { name: "note".to_string(), val: "".into() })
+        })));
+    }
+    
+    /// index of each variant. the match is exhaustive on purpose: adding a variant to `Parameter`
+    /// breaks the build here until it's given an index and an example below
+    fn variant_index(param: &Parameter) -> usize {
+        match param {
+            Parameter::Email { .. } => 0,
+            Parameter::YesNo { .. } => 1,
+            Parameter::CellPhone { .. } => 2,
+            Parameter::Choose { .. } => 3,
+            Parameter::Repeat { .. } => 4,
+            Parameter::Date { .. } => 5,
+            Parameter::CheckBoxes { .. } => 6,
+            Parameter::String { .. } => 7,
+            Parameter::Maybe { .. } => 8,
+            Parameter::Static { .. } => 9,
+            Parameter::Name { .. } => 10,
+            Parameter::File { .. } => 11,
+            Parameter::Regex { .. } => 12,
+            Parameter::Int { .. } => 13,
+            Parameter::Float { .. } => 14,
+            Parameter::Money { .. } => 15,
+            Parameter::Time { .. } => 16,
+            Parameter::DateTime { .. } => 17,
+            Parameter::Let { .. } => 18,
+            Parameter::Template { .. } => 19,
+            Parameter::Csv { .. } => 20,
+            Parameter::Weighted { .. } => 21,
+            Parameter::Payload { .. } => 22,
+            Parameter::Unique { .. } => 23,
+            Parameter::Counter { .. } => 24,
+            Parameter::Uuid { .. } => 25,
+            Parameter::PersonName { .. } => 26,
+            Parameter::Address { .. } => 27,
+            Parameter::Phone { .. } => 28,
+            Parameter::Text { .. } => 29,
+            Parameter::Password { .. } => 30,
+            Parameter::Checksum { .. } => 31,
+        }
+    }
+    const VARIANT_COUNT: usize = 32;
+    
+    #[test]
+    fn test_every_variant_has_a_parse_path() {
+        let examples = [
+            "email(\"e\",[\"x.com\"])",
+            "yes_no(\"y\")",
+            "cellphone(\"c\")",
+            "choose_any([(\"k\",\"v\")])",
+            "choose_n(1,[(\"k\",\"v\")])",
+            "date(\"d\",1990,2000)",
+            "time(\"t\")",
+            "datetime(\"dt\",now-30d,now)",
+            "let v = string(\"v\",5)",
+            "template(\"t\",\"#{int(1,9)}\")",
+            "csv(\"a.csv\",[(\"email\",\"user\")],unique)",
+            "one_of(yes_no(\"y\"),static(\"y\",\"\"))",
+            "repeat(1,3,string(\"tag\",5))",
+            "maybe(0.3,cellphone(\"p\"))",
+            "unique(email(\"e\",[\"x.com\"]))",
+            "weighted([(\"plan\",\"free\",70),(\"plan\",\"pro\",30)])",
+            "checkboxes([(\"k\",\"v\")])",
+            "string(\"s\",10)",
+            "optional(\"o\")",
+            "static(\"k\",\"v\")",
+            "static_str(\"k\",\"v\")",
+            "name(\"n\",10)",
+            "file(\"f\",bytes(1,10))",
+            "regex(\"r\",\"[a-z]+\")",
+            "payload(\"p\",crlf)",
+            "counter(\"id\",1000,5)",
+            "seq(\"ref\",\"INV-{:06}\")",
+            "uuid(\"rid\",v7,fresh)",
+            "first_name(\"first\",de_DE)",
+            "last_name(\"last\",ES)",
+            "address([(\"street\",\"street\"),(\"postcode\",\"zip\")],en_GB)",
+            "phone(\"tel\",JP)",
+            "text(\"message\",5,40,markov(\"corpus.txt\"))",
+            "card(\"cc\",visa,invalid)",
+            "iban(\"iban\",GB)",
+            "isbn(\"isbn\")",
+            "ean(\"barcode\",invalid)",
+            "password(\"pw\",12,20,[upper,digits(2),symbols,forbid(\"\\\"'\")])",
+            "int(\"i\",-5,5)",
+            "float(\"f\",0.5,1.5,2)",
+            "money(\"m\",1,100,\"USD\")",
+        ];
+        let mut seen = [false; VARIANT_COUNT];
+        for example in examples {
+            for param in parse_config(example).unwrap_or_else(|e| panic!("{example} failed to parse: {e:?}")) {
+                seen[variant_index(&param)] = true;
+            }
+        }
+        for (i, seen) in seen.iter().enumerate() {
+            assert!(seen, "variant {i} of `Parameter` has no example that parses to it");
+        }
+        
+        // and every advertised generator name is actually accepted
+        for name in GENERATORS {
+            assert!(
+                examples.iter().any(|e| e.starts_with(&format!("{name}("))),
+                "`{name}` is listed in GENERATORS but has no example"
+            );
+        }
+    }
+    
+    #[test]
+    fn test_parse_file() {
+        assert_eq!(
+            parse_file("file(\"avatar\", path(\"cat.png\"))"),
+            Ok(("", Parameter::File { name: "avatar".to_string(), source: FileSource::Path(Uploads::new(PathBuf::from("cat.png"))), content_type: None }))
+        );
+        assert_eq!(
+            parse_file("file(\"doc\",dir(\"uploads\"),\"application/pdf\")"),
+            Ok(("", Parameter::File {
+                name: "doc".to_string(),
+                source: FileSource::Dir(Uploads::new(PathBuf::from("uploads"))),
+                content_type: Some("application/pdf".to_string())
+            }))
+        );
+        assert_eq!(
+            parse_file("file(\"blob\", bytes(16, 1024))"),
+            Ok(("", Parameter::File { name: "blob".to_string(), source: FileSource::Random { min_size: 16, max_size: 1024, distribution: Distribution::Uniform }, content_type: None }))
+        );
+        assert!(parse_config("file(\"blob\", bytes(10, 1))").is_err());
+        assert_eq!(
+            parse_config("file(\"blob\", url(\"x\"))").unwrap_err().expected.unwrap(),
+            "file source: `path(\"file\")`, `dir(\"directory\")` or `bytes(min,max)` in `file(...)`"
+        );
+    }
+    
+    #[test]
+    fn test_parse_regex() {
+        assert_eq!(
+            parse_regex("regex(\"sku\", \"[A-Z]{2}-\\d{4}\")"),
+            Ok(("", Parameter::Regex { name: "sku".to_string(), pattern: Pattern::parse("[A-Z]{2}-\\d{4}").unwrap() }))
+        );
+        assert_eq!(parse_raw_string("\"a\\\"b\\\\c\""), Ok(("", "a\"b\\\\c".to_string())));
+        let err = parse_config("regex(\"sku\", \"[A-Z\")").unwrap_err();
+        assert_eq!(err.input, "\"[A-Z\")");
+        assert_eq!(err.expected.unwrap(), "a valid regular expression in `regex(...)` (unclosed character class)");
+        assert!(parse_config(r#"regex("r","^(^a|b$)$")"#).is_ok());
+        for (source, reason) in [
+            (r"\bword\b", "word boundaries such as `\\b` can't be generated"),
+            ("a|b^", "`^` that isn't at the start of the pattern"),
+            ("(a$|b)c", "`$` that isn't at the end of the pattern"),
+            ("(^a)+", "`^` that isn't at the start of the pattern"),
+            ("a(?=b)", "look-around, including look-ahead and look-behind, is not supported"),
+        ] {
+            let config = format!("regex(\"r\",\"{source}\")");
+            let err = parse_config(&config).unwrap_err();
+            assert_eq!(err.expected.unwrap(), format!("a valid regular expression in `regex(...)` ({reason})"), "{source}");
+        }
+    }
+    
+    #[test]
+    fn test_parse_numbers() {
+        assert_eq!(
+            parse_int("int(\"qty\", -1, 100)"),
+            Ok(("", Parameter::Int { name: "qty".to_string(), min: -1, max: 100, distribution: Distribution::Uniform }))
+        );
+        assert_eq!(
+            parse_float("float(\"weight\",0.1,99.9,2, normal(20, 7.5))"),
+            Ok(("", Parameter::Float {
+                name: "weight".to_string(), min: 0.1, max: 99.9, precision: 2,
+                distribution: Distribution::Normal { mean: 20.0, sd: 7.5 }
+            }))
+        );
+        assert_eq!(
+            parse_money("money(\"amount\",1,5000,\"USD\",log_uniform)"),
+            Ok(("", Parameter::Money {
+                name: "amount".to_string(), min: 1.0, max: 5000.0, currency: "USD".to_string(),
+                distribution: Distribution::LogUniform
+            }))
+        );
+        assert_eq!(
+            parse_config("int(\"qty\",10,1)").unwrap_err().expected.unwrap(),
+            "max no smaller than min in `int(...)`"
+        );
+        assert_eq!(
+            parse_config("float(\"f\",0,1,400)").unwrap_err().expected.unwrap(),
+            "precision (a whole number up to 15) in `float(...)`"
+        );
+        assert!(parse_config("float(\"f\",0,1,15)").is_ok());
+        let err = parse_config("float(\"f\",0.11,0.12,1)").unwrap_err();
+        assert_eq!(err.input, "0.11,0.12,1)");
+        assert_eq!(err.expected.unwrap(), "min and max with a number that has `precision` decimals between them in `float(...)`");
+        assert!(parse_config("float(\"f\",0.11,0.11,2)").is_ok());
+        assert_eq!(
+            parse_config("money(\"m\",1.2,1.8,\"JPY\")").unwrap_err().expected.unwrap(),
+            "min and max with an amount in whole units of the currency between them in `money(...)`"
+        );
+        let err = parse_config("float(\"f\",-1e308,1e308,2)").unwrap_err();
+        assert_eq!(err.input, "-1e308,1e308,2)");
+        assert_eq!(err.expected.unwrap(), "min and max close enough that `max - min` is a finite number in `float(...)`");
+        assert_eq!(
+            parse_config("money(\"m\",-1.7e308,1.7e308,\"USD\")").unwrap_err().expected.unwrap(),
+            "min and max close enough that `max - min` is a finite number in `money(...)`"
+        );
+        let err = parse_config("counter(\"c\",1,0)").unwrap_err();
+        assert_eq!(err.input, "0)");
+        assert_eq!(err.expected.unwrap(), "step other than 0 in `counter(...)`");
+        assert_eq!(
+            parse_config("int(\"qty\",1,10,pareto)").unwrap_err().expected.unwrap(),
+            format!("distribution: {DISTRIBUTIONS}")
+        );
+    }
+    
+    #[test]
+    fn test_parse_length_distributions() {
+        assert_eq!(
+            parse_string_entry("string(\"bio\",500,zipf(1.1))"),
+            Ok(("", Parameter::String { name: "bio".to_string(), max_len: 500, length: Distribution::Zipf { exponent: 1.1 } }))
+        );
+        assert_eq!(
+            parse_name("name(\"name\",12,fixed(6))"),
+            Ok(("", Parameter::Name { name: "name".to_string(), max_len: 12, locale: None, length: Distribution::Fixed(6.0) }))
+        );
+        assert_eq!(
+            parse_name("name(\"name\",12,DE)"),
+            Ok(("", Parameter::Name { name: "name".to_string(), max_len: 12, locale: Some(Locale::DeDe), length: Distribution::Fixed(12.0) }))
+        );
+        assert_eq!(
+            parse_name("name(\"name\",12,DE,zipf(1.5))"),
+            Ok(("", Parameter::Name { name: "name".to_string(), max_len: 12, locale: Some(Locale::DeDe), length: Distribution::Zipf { exponent: 1.5 } }))
+        );
+        assert_eq!(
+            parse_text("text(\"body\",1,400,histogram(\"sizes.csv\"))"),
+            Ok(("", Parameter::Text {
+                name: "body".to_string(), min: 1, max: 400, style: TextStyle::Lorem,
+                length: Distribution::Histogram(Histogram::new(PathBuf::from("sizes.csv")))
+            }))
+        );
+        assert_eq!(
+            parse_text("text(\"body\",1,400,lorem,normal(40,10))"),
+            Ok(("", Parameter::Text {
+                name: "body".to_string(), min: 1, max: 400, style: TextStyle::Lorem,
+                length: Distribution::Normal { mean: 40.0, sd: 10.0 }
+            }))
+        );
+        assert_eq!(
+            parse_password("password(\"pw\",8,64,[digits],fixed(12))"),
+            Ok(("", Parameter::Password {
+                name: "pw".to_string(), min: 8, max: 64,
+                policy: PasswordPolicy { digits: 1, ..PasswordPolicy::default() },
+                length: Distribution::Fixed(12.0)
+            }))
+        );
+        assert_eq!(
+            parse_file("file(\"doc\",bytes(1,4096,exponential(512)))"),
+            Ok(("", Parameter::File {
+                name: "doc".to_string(),
+                source: FileSource::Random { min_size: 1, max_size: 4096, distribution: Distribution::Exponential { mean: 512.0 } },
+                content_type: None
+            }))
+        );
+        let err = parse_config("string(\"s\",0)").unwrap_err();
+        assert_eq!(err.input, "0)");
+        assert_eq!(err.expected.unwrap(), "max length from 1 to 4194304 in `string(...)`");
+        assert_eq!(
+            parse_config("name(\"n\",0,DE)").unwrap_err().expected.unwrap(),
+            "max length from 1 to 4194304 in `name(...)`"
+        );
+        let err = parse_config("name(\"name\",12,zipfian)").unwrap_err();
+        assert_eq!(err.input, "zipfian)");
+        assert!(err.expected.unwrap().starts_with("locale (en_US"));
+        assert_eq!(
+            parse_config("text(\"t\",1,5,zipf(0))").unwrap_err().expected.unwrap(),
+            "exponent larger than 0 in `zipf(...)`"
+        );
+    }
+    
+    #[test]
+    fn test_parse_let_and_ref() {
+        let config = r#"
+            let pw = string("pw", 12)
+            email("email", ["x.com"])
+            static("password", ref("pw"))
+            static("confirm_email", ref( "email" , lower ))
+        "#;
+        assert_eq!(parse_config(config), Ok(vec![
+            Parameter::Let { var: "pw".to_string(), param: Box::new(Parameter::String { name: "pw".to_string(), max_len: 12, length: Distribution::Uniform }) },
+            Parameter::Email { name: "email".to_string(), domains: vec!["x.com".to_string()], length: Distribution::Uniform },
+            Parameter::Static { name: "password".to_string(), val: Value::Ref { name: "pw".to_string(), transform: Transform::None } },
+            Parameter::Static { name: "confirm_email".to_string(), val: Value::Ref { name: "email".to_string(), transform: Transform::Lower } },
+        ]));
+        
+        let config = "static(\"confirm\", ref(\"email\"))\nemail(\"email\", [\"x.com\"])";
+        let err = parse_config(config).unwrap_err();
+        assert_eq!(err.input, config);
+        assert_eq!(err.expected.unwrap(), "`ref(\"email\")` to name a field or `let` defined earlier in the config");
+        assert_eq!(parse_config("let 1x = yes_no(\"a\")").unwrap_err().expected.unwrap(), "variable name after `let`");
+        
+        let config = "let pw = string(\"pw\", 12)\nyes_no(\"a\")\nlet pw = string(\"pw\", 8)";
+        let err = parse_config(config).unwrap_err();
+        assert_eq!(err.input, "let pw = string(\"pw\", 8)");
+        assert_eq!(err.expected.unwrap(), "a `let` name that isn't taken yet, `pw` is already defined at line 1");
+    }
+    
+    #[test]
+    fn test_parse_template() {
+        assert_eq!(
+            parse_template(r#"template("subject", "Order {int(1, 999)} from {ref: name}\n{{}}{yes_no()}")"#),
+            Ok(("", Parameter::Template { name: "subject".to_string(), parts: vec![
+                TemplatePart::Value("Order ".into()),
+                TemplatePart::Generator(Parameter::Int { name: String::new(), min: 1, max: 999, distribution: Distribution::Uniform }),
+                TemplatePart::Value(" from ".into()),
+                TemplatePart::Value(Value::Ref { name: "name".to_string(), transform: Transform::None }),
+                TemplatePart::Value("\n{}".into()),
+                TemplatePart::Generator(Parameter::YesNo { name: String::new(), probability: 0.5 }),
+            ] }))
+        );
+        
+        // errors inside an inline generator point into the config, not at the template
+        let config = r#"template("t", "{int(1, x)}")"#;
+        let err = parse_config(config).unwrap_err();
+        assert_eq!(err.offset_in(config), config.find('x').unwrap());
+        assert_eq!(err.expected.unwrap(), "max (an integer) in `int(...)`");
+        
+        let config = r#"template("t", "{nope(1)}")"#;
+        let err = parse_config(config).unwrap_err();
+        assert_eq!(err.offset_in(config), config.find("nope").unwrap());
+        assert!(err.expected.unwrap().starts_with("a generator, one of:"));
+        
+        let config = r#"template("t", "{int(1,2) x")"#;
+        assert_eq!(parse_config(config).unwrap_err().expected.unwrap(), "`}` to close `{` in template");
+        
+        let config = r#"template("t", "hi {ref:t}")"#;
+        assert_eq!(
+            parse_config(config).unwrap_err().expected.unwrap(),
+            "`ref(\"t\")` to name a field or `let` defined earlier in the config"
+        );
+    }
+    
+    #[test]
+    fn test_parse_csv() {
+        assert_eq!(
+            parse_csv(r#"csv("data/accounts.csv", [("name","full_name"), ("email","email"),], random)"#),
+            Ok(("", Parameter::Csv {
+                dataset: Dataset::new(PathBuf::from("data/accounts.csv")),
+                columns: vec![
+                    ("name".to_string(), "full_name".to_string()),
+                    ("email".to_string(), "email".to_string())
+                ],
+                order: RowOrder::Random
+            }))
+        );
+        let params = parse_config(r#"csv("a.csv", [("phone","tel")]) static("copy", ref("tel"))"#).unwrap();
+        assert!(matches!(params[0], Parameter::Csv { order: RowOrder::Sequential, .. }));
+        assert_eq!(
+            parse_config(r#"csv("a.csv", [("phone","tel")], once)"#).unwrap_err().expected.unwrap(),
+            "row order: `sequential`, `random` or `unique` in `csv(...)`"
+        );
+        for config in [
+            r#"maybe(1.0,csv("a.csv",[("n","n")],unique))"#,
+            r#"csv("a.csv",[("n","n")],unique)?"#,
+            r#"repeat(1,2,csv("a.csv",[("n","n")],unique))"#,
+            r#"let n = csv("a.csv",[("n","n")],unique)"#,
+            r#"fragment f { one_of(csv("a.csv",[("n","n")],unique), static("n","x")) } use f"#,
+        ] {
+            assert_eq!(
+                parse_config(config).unwrap_err().expected.unwrap(),
+                "a csv with `unique` rows on a line of its own, not inside another generator or a `let`",
+                "{config}"
+            );
+        }
+        assert!(parse_config(r#"maybe(0.5,csv("a.csv",[("n","n")],random))"#).is_ok());
+    }
+    
+    #[test]
+    fn test_fragments_prefix_their_fields() {
+        let config = r#"
+            let site = regex("s", "[a-z]+")
+            fragment contact {
+                email("email", ["x.com"])
+                static("confirm_email", ref("email", lower))
+                static("site", ref("site"))
+            }
+            fragment order { use contact("buyer_") yes_no("gift") }
+            use contact("billing_")
+            use order("o_")
+            use contact
+        "#;
+        let params = parse_config(config).unwrap();
+        let names: Vec<&str> = params.iter().flat_map(Parameter::field_names).collect();
+        assert_eq!(names, [
+            "site",
+            "billing_email", "billing_confirm_email", "billing_site",
+            "o_buyer_email", "o_buyer_confirm_email", "o_buyer_site", "o_gift",
+            "email", "confirm_email", "site",
+        ]);
+        assert_eq!(params[5], Parameter::Static {
+            name: "o_buyer_confirm_email".to_string(),
+            val: Value::Ref { name: "o_buyer_email".to_string(), transform: Transform::Lower }
+        });
+        assert_eq!(params[6], Parameter::Static {
+            name: "o_buyer_site".to_string(),
+            val: Value::Ref { name: "site".to_string(), transform: Transform::None }
+        });
+        
+        let config = "use contact(\"a_\")";
+        let err = parse_config(config).unwrap_err();
+        assert_eq!(err.input, config);
+        assert_eq!(err.expected.unwrap(), "`use contact` to name a fragment defined earlier");
+        
+        // refs to names outside the fragment are checked where it's used
+        let config = "fragment f { static(\"copy\", ref(\"email\")) }\nuse f";
+        let err = parse_config(config).unwrap_err();
+        assert_eq!(err.offset_in(config), config.find("use f").unwrap());
+        
+        assert_eq!(
+            parse_config("include(\"common.form\")").unwrap_err().expected.unwrap(),
+            "a generator, `include(...)` only works in config files"
+        );
+        assert!(parse_config("fragment f { yes_no(\"a\") } fragment f { yes_no(\"b\") }").is_err());
+    }
+    
+    #[test]
+    fn test_parse_nested_generators() {
+        assert_eq!(
+            parse_config(r#"
+                one_of(email("e", ["x.com"]), static("e", ""))
+                repeat(1, 5, string("tag", 10))
+                maybe(0.3, one_of(cellphone("p"), static("p", "n/a")))
+            "#),
+            Ok(vec![
+                Parameter::Choose { n: 1, options: vec![
+                    Parameter::Email { name: "e".to_string(), domains: vec!["x.com".to_string()], length: Distribution::Uniform },
+                    Parameter::Static { name: "e".to_string(), val: "".into() },
+                ] },
+                Parameter::Repeat { min: 1, max: 5, param: Box::new(Parameter::String { name: "tag".to_string(), max_len: 10, length: Distribution::Uniform }) },
+                Parameter::Maybe { probability: 0.3, param: Box::new(Parameter::Choose { n: 1, options: vec![
+                    Parameter::CellPhone { name: "p".to_string() },
+                    Parameter::Static { name: "p".to_string(), val: "n/a".into() },
+                ] }) },
+            ])
+        );
+        assert_eq!(
+            parse_config("maybe(1.5, yes_no(\"a\"))").unwrap_err().expected.unwrap(),
+            "probability (a number from 0 to 1) in `maybe(...)`"
+        );
+        assert_eq!(
+            parse_config("repeat(3, 1, yes_no(\"a\"))").unwrap_err().expected.unwrap(),
+            "max no smaller than min in `repeat(...)`"
+        );
+        let config = "one_of(yes_no(\"a\"), 5)";
+        let err = parse_config(config).unwrap_err();
+        assert_eq!(err.offset_in(config), config.find('5').unwrap());
+        assert!(err.expected.unwrap().starts_with("a generator, one of:"));
+        assert_eq!(
+            parse_config("unique(one_of(file(\"f\",bytes(1,10)), static(\"f\",\"\")))").unwrap_err().expected.unwrap(),
+            "a generator that uploads no files in `unique(...)`"
+        );
+    }
+    
+    #[test]
+    fn test_parse_probabilities_and_weights() {
+        let static_param = |name: &str, val: &str| Parameter::Static { name: name.to_string(), val: val.into() };
+        assert_eq!(
+            parse_config(r#"
+                weighted([("plan","free",70), ("plan","pro",25.5), ("plan","ent",0)])
+                cellphone("phone")? 0.25
+                static("ref", "x")?
+                yes_no("agree", 0.9)
+                optional("note", 0.1)
+            "#),
+            Ok(vec![
+                Parameter::Weighted { options: vec![
+                    (70.0, static_param("plan", "free")),
+                    (25.5, static_param("plan", "pro")),
+                    (0.0, static_param("plan", "ent")),
+                ] },
+                Parameter::Maybe { probability: 0.25, param: Box::new(Parameter::CellPhone { name: "phone".to_string() }) },
+                Parameter::Maybe { probability: 0.5, param: Box::new(static_param("ref", "x")) },
+                Parameter::YesNo { name: "agree".to_string(), probability: 0.9 },
+                Parameter::Maybe { probability: 0.1, param: Box::new(static_param("note", "")) },
+            ])
+        );
+        assert_eq!(
+            parse_config("yes_no(\"a\")?1.2").unwrap_err().expected.unwrap(),
+            "presence probability (a number from 0 to 1) after `?`"
+        );
+        assert_eq!(
+            parse_config("weighted([(\"plan\",\"free\",0)])").unwrap_err().expected.unwrap(),
+            "at least one weight larger than 0 in `weighted(...)`"
+        );
+        assert_eq!(
+            parse_config("weighted([(\"plan\",\"free\",-1)])").unwrap_err().expected.unwrap(),
+            "weight (a number no smaller than 0) in `weighted(...)`"
+        );
+        assert_eq!(
+            parse_config("weighted([(\"a\",\"b\",1e308),(\"a\",\"c\",1e308)])").unwrap_err().expected.unwrap(),
+            "weights with a finite sum in `weighted(...)`"
         );
     }
 }
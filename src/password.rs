@@ -0,0 +1,86 @@
+use rand::{Rng, rngs::StdRng, seq::SliceRandom};
+
+use crate::Distribution;
+
+const LOWER: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPER: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+/// what a `password(...)` has to contain. with no rules the password is letters and digits
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PasswordPolicy {
+    /// least number of characters of each class
+    pub upper: usize,
+    pub lower: usize,
+    pub digits: usize,
+    pub symbols: usize,
+    /// characters that never appear, whatever class they're in
+    pub forbidden: String,
+}
+
+impl PasswordPolicy {
+    fn classes(&self) -> [(&'static str, usize); 4] {
+        [(LOWER, self.lower), (UPPER, self.upper), (DIGITS, self.digits), (SYMBOLS, self.symbols)]
+    }
+
+    fn allowed(&self, class: &str) -> Vec<char> {
+        class.chars().filter(|c| !self.forbidden.contains(*c)).collect()
+    }
+
+    /// characters the rest of the password is filled with: letters, digits, and symbols if the
+    /// policy asks for any
+    fn pool(&self) -> Vec<char> {
+        self.classes().iter()
+            .filter(|(class, least)| *class != SYMBOLS || *least > 0)
+            .flat_map(|(class, _)| self.allowed(class))
+            .collect()
+    }
+
+    /// least length a password needs to follow the policy
+    pub fn min_len(&self) -> usize {
+        self.classes().iter().map(|(_, least)| least).sum()
+    }
+
+    /// whether a password of at most `max_len` characters can follow the policy
+    pub fn is_satisfiable(&self, max_len: usize) -> bool {
+        self.min_len() <= max_len
+            && !self.pool().is_empty()
+            && self.classes().iter().all(|(class, least)| *least == 0 || !self.allowed(class).is_empty())
+    }
+
+    /// password of `min..=max` characters following the policy, with the length drawn from `length`.
+    /// parsing makes sure the policy fits in `max` characters
+    pub(crate) fn generate(&self, rng: &mut StdRng, min: usize, max: usize, length: &Distribution) -> String {
+        let len = length.sample_len(rng, min.max(self.min_len()), max);
+        let mut password = Vec::with_capacity(len);
+        for (class, least) in self.classes() {
+            let allowed = self.allowed(class);
+            password.extend((0..least).map(|_| allowed[rng.random_range(0..allowed.len())]));
+        }
+        let pool = self.pool();
+        while password.len() < len {
+            password.push(pool[rng.random_range(0..pool.len())]);
+        }
+        password.shuffle(rng);
+        password.into_iter().collect()
+    }
+
+    /// password breaking the policy: shorter than `min`, or without a character the policy
+    /// requires. nothing if the policy can't be broken
+    pub(crate) fn generate_weak(&self, rng: &mut StdRng, min: usize, max: usize) -> Option<String> {
+        let required: Vec<&str> = self.classes().iter().filter(|(_, least)| *least > 0).map(|(class, _)| *class).collect();
+        if min > 1 && (required.is_empty() || rng.random_bool(0.5)) {
+            let pool = self.pool();
+            let len = rng.random_range(1..min);
+            return Some((0..len).map(|_| pool[rng.random_range(0..pool.len())]).collect());
+        }
+        let missing = *required.get(rng.random_range(0..required.len().max(1)))?;
+        let pool: Vec<char> = self.pool().into_iter().filter(|c| !missing.contains(*c)).collect();
+        if pool.is_empty() {
+            return None;
+        }
+        let len = rng.random_range(min.max(1)..=max.max(1));
+        Some((0..len).map(|_| pool[rng.random_range(0..pool.len())]).collect())
+    }
+}
@@ -0,0 +1,175 @@
+use rand::{Rng, rngs::StdRng};
+
+/// built-in corpus a `payload(...)` field draws its value from. these are the inputs that tend to
+/// break hand-written multipart parsers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PayloadSet {
+    /// combining marks, bidi overrides, emoji and astral-plane characters, NUL and control characters
+    Unicode,
+    /// single tokens without any whitespace, from 1 KiB up to 1 MiB
+    Long,
+    /// CR and LF alone, in pairs and in runs, including header-like lines after them
+    Crlf,
+    /// `--` followed by the boundary of the form, or something close to it
+    Boundary,
+    /// malformed, double and overlong percent-encodings
+    Percent,
+    /// any of the other sets
+    All,
+}
+
+impl PayloadSet {
+    pub const NAMES: &[(&str, PayloadSet)] = &[
+        ("unicode", PayloadSet::Unicode),
+        ("long", PayloadSet::Long),
+        ("crlf", PayloadSet::Crlf),
+        ("boundary", PayloadSet::Boundary),
+        ("percent", PayloadSet::Percent),
+        ("all", PayloadSet::All),
+    ];
+
+    /// a payload out of the set. `boundary` is the boundary the form is sent with, or empty if it
+    /// isn't known, in which case boundary payloads make up one of their own
+    pub(crate) fn generate(self, rng: &mut StdRng, boundary: &str) -> String {
+        match self {
+            PayloadSet::Unicode => pick(rng, UNICODE).to_string(),
+            PayloadSet::Crlf => pick(rng, CRLF).to_string(),
+            PayloadSet::Percent => pick(rng, PERCENT).to_string(),
+            PayloadSet::Long => long_token(rng),
+            PayloadSet::Boundary => boundary_like(rng, boundary),
+            PayloadSet::All => {
+                let sets = [PayloadSet::Unicode, PayloadSet::Long, PayloadSet::Crlf, PayloadSet::Boundary, PayloadSet::Percent];
+                sets[rng.random_range(0..sets.len())].generate(rng, boundary)
+            }
+        }
+    }
+}
+
+fn pick<'a>(rng: &mut StdRng, corpus: &[&'a str]) -> &'a str {
+    corpus[rng.random_range(0..corpus.len())]
+}
+
+const UNICODE: &[&str] = &[
+    // combining marks, alone, stacked and on top of nothing
+    "e\u{301}",
+    "\u{301}",
+    "Z\u{335}\u{321}\u{31b}\u{349}a\u{337}\u{316}l\u{336}\u{33a}g\u{337}\u{324}o\u{335}\u{31e}",
+    "a\u{300}\u{301}\u{302}\u{303}\u{304}\u{305}\u{306}\u{307}\u{308}\u{309}\u{30a}\u{30b}\u{30c}\u{30d}\u{30e}\u{30f}",
+    // bidi overrides and isolates
+    "\u{202e}gnp.exe",
+    "invoice\u{202e}fdp.exe",
+    "\u{202d}abc\u{202c}",
+    "\u{2067}\u{5e9}\u{5dc}\u{5d5}\u{5dd}\u{2069} hello",
+    "\u{200f}\u{627}\u{644}\u{639}\u{631}\u{628}\u{64a}\u{629}\u{200e}",
+    // invisible characters
+    "\u{200b}",
+    "zero\u{200b}width\u{200c}non\u{200d}joiner",
+    "\u{feff}bom",
+    "soft\u{ad}hyphen",
+    "\u{2028}line\u{2029}paragraph",
+    // emoji and other characters outside the basic multilingual plane
+    "\u{1f600}",
+    "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}\u{200d}\u{1f466}",
+    "\u{1f1fa}\u{1f1f8}\u{1f1ef}\u{1f1f5}",
+    "\u{1f44d}\u{1f3fd}",
+    "\u{2764}\u{fe0f}",
+    "\u{1d573}\u{1d58a}\u{1d591}\u{1d591}\u{1d594}",
+    "\u{2070e}\u{20731}\u{20779}",
+    "\u{10ffff}",
+    "\u{fffd}\u{ffff}\u{fffe}",
+    // NUL and control characters
+    "\0",
+    "before\0after",
+    "\u{1}\u{2}\u{3}\u{4}\u{5}\u{6}\u{7}\u{8}",
+    "\u{1b}[31mred\u{1b}[0m",
+    "tab\there\u{b}vertical\u{c}form feed",
+    "\u{7f}",
+    "\u{85}next line",
+    "\u{9b}31m",
+    // case mapping and normalization changes the length
+    "\u{130}stanbul",
+    "stra\u{df}e",
+    "\u{fb03}",
+    "\u{ff1c}script\u{ff1e}",
+    "\u{212b}\u{c5}A\u{30a}",
+];
+
+const CRLF: &[&str] = &[
+    "\r\n",
+    "\r",
+    "\n",
+    "\r\n\r\n",
+    "\n\r",
+    "first\r\nsecond",
+    "first\rsecond",
+    "first\nsecond",
+    "trailing\r\n",
+    "trailing\r",
+    "\r\n\r\n\r\n\r\n\r\n\r\n\r\n\r\n",
+    "value\r\nContent-Disposition: form-data; name=\"injected\"\r\n\r\nsmuggled",
+    "value\r\n\r\nContent-Type: text/html",
+    "header: value\r\n",
+    "\r\n\t folded",
+];
+
+const PERCENT: &[&str] = &[
+    "%",
+    "%%",
+    "100%",
+    "%2",
+    "%zz",
+    "%G0",
+    "%00",
+    "%0D%0A",
+    "%0d%0a",
+    "%252F",
+    "%25252525",
+    "%C0%AE%C0%AE%2F",
+    "%C0%80",
+    "%FF%FE",
+    "%E2%80%AE",
+    "%ED%A0%80",
+    "%u0041",
+    "%%32%65",
+    "a+b%20c",
+    "+",
+    "%2B%20+",
+    "..%2F..%2Fetc%2Fpasswd",
+];
+
+/// sizes of the `long` tokens, the bigger ones being less common
+const LONG_SIZES: &[usize] = &[1 << 10, 1 << 10, 8 << 10, 8 << 10, 64 << 10, 1 << 20];
+
+fn long_token(rng: &mut StdRng) -> String {
+    let size = LONG_SIZES[rng.random_range(0..LONG_SIZES.len())];
+    match rng.random_range(0..3) {
+        0 => "A".repeat(size),
+        // three bytes a character
+        1 => "\u{20ac}".repeat(size.div_ceil(3)),
+        _ => {
+            let chars = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+            (0..size).map(|_| chars[rng.random_range(0..chars.len())] as char).collect()
+        }
+    }
+}
+
+fn boundary_like(rng: &mut StdRng, boundary: &str) -> String {
+    let boundary = match boundary {
+        "" => crate::Form::random_boundary(rng),
+        boundary => boundary.to_string(),
+    };
+    // every boundary is ascii, so it can be cut anywhere
+    let shorter = &boundary[..boundary.len() - 1];
+    match rng.random_range(0..10) {
+        0 => format!("--{boundary}"),
+        1 => format!("\r\n--{boundary}"),
+        2 => format!("\r\n--{boundary}--"),
+        3 => format!("--{boundary}--\r\n"),
+        4 => format!("\r\n--{boundary}\r\nContent-Disposition: form-data; name=\"smuggled\"\r\n\r\nvalue"),
+        5 => format!("--{shorter}"),
+        6 => format!("\r\n--{boundary}x"),
+        7 => format!("--{}", boundary.to_uppercase()),
+        8 => boundary,
+        _ => ["--", "----", "\r\n--", "\r\n----\r\n"][rng.random_range(0..4)].to_string(),
+    }
+}
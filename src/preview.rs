@@ -0,0 +1,79 @@
+use std::io::Write;
+
+use clap::ValueEnum;
+
+use crate::{FieldValue, FormGenerator, spammer::VIOLATION_HEADER};
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum PreviewFormat {
+    /// one json object per form: {"index": n, "fields": [{"name": .., "value": ..}, ..]}.
+    /// file parts have filename, content_type and size instead of a value. with invalid values
+    /// the object also has a "violation"
+    Json,
+    /// one `index,name,value` row per field. the value of a file part is a short description of it.
+    /// with invalid values there's a fourth `violation` column
+    Csv,
+    /// the raw multipart body the spammer would send, preceded by its Content-Type header and,
+    /// with invalid values, the violation header
+    Multipart,
+}
+
+/// writes forms `start..start + count` of `generator` to `out` without sending anything. stops
+/// early if the generator runs out of forms or of unique values
+pub fn write_forms<W: Write>(out: &mut W, generator: &FormGenerator, start: u64, count: u64, format: PreviewFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let end = start.saturating_add(count).min(generator.limit().unwrap_or(u64::MAX));
+    let indices = start..end;
+    match format {
+        PreviewFormat::Json => {
+            for index in indices {
+                let Some(form) = generator.try_generate(index) else { break };
+                let fields: Vec<serde_json::Value> = form.fields.iter().map(|(name, value)| match value {
+                    FieldValue::Text(text) => serde_json::json!({ "name": name, "value": text }),
+                    FieldValue::File { filename, content_type, data } => serde_json::json!({
+                        "name": name, "filename": filename, "content_type": content_type, "size": data.len()
+                    }),
+                }).collect();
+                // written by hand so `index` comes first, serde_json sorts object keys
+                let violation = match &form.violation {
+                    Some(violation) => format!(",\"violation\":{}", serde_json::Value::String(violation.to_string())),
+                    None => String::new(),
+                };
+                writeln!(out, "{{\"index\":{index}{violation},\"fields\":{}}}", serde_json::Value::Array(fields))?;
+            }
+        }
+        PreviewFormat::Csv => {
+            let mut csv_file = csv::Writer::from_writer(&mut *out);
+            let invalid = generator.invalid_values();
+            let header = ["index", "name", "value", "violation"];
+            csv_file.write_record(if invalid { &header[..] } else { &header[..3] })?;
+            for index in indices {
+                let Some(form) = generator.try_generate(index) else { break };
+                let violation = form.violation.map(|violation| violation.to_string()).unwrap_or_default();
+                for (name, value) in form.fields {
+                    let value = match value {
+                        FieldValue::Text(text) => text,
+                        FieldValue::File { filename, content_type, data } => {
+                            format!("{filename} ({content_type}, {} bytes)", data.len())
+                        }
+                    };
+                    let row = [index.to_string(), name, value, violation.clone()];
+                    csv_file.write_record(if invalid { &row[..] } else { &row[..3] })?;
+                }
+            }
+            csv_file.flush()?;
+        }
+        PreviewFormat::Multipart => {
+            for index in indices {
+                let Some(form) = generator.try_generate(index) else { break };
+                write!(out, "Content-Type: {}\r\n", form.content_type())?;
+                if let Some(violation) = &form.violation {
+                    write!(out, "{VIOLATION_HEADER}: {violation}\r\n")?;
+                }
+                write!(out, "\r\n")?;
+                out.write_all(&form.body())?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use rand::{Rng, rngs::StdRng};
+use regex_syntax::hir::{Class, ClassUnicode, ClassUnicodeRange, Hir, HirKind, Look};
+
+/// how many extra repetitions `*`, `+` and `{n,}` may produce on top of their minimum
+const UNBOUNDED_EXTRA: u32 = 8;
+
+/// a regular expression, parsed once when the config is loaded, that strings are generated from
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    source: String,
+    hir: Hir,
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Pattern {
+    /// parses `source`, returning a one line description of the problem if it isn't a valid regex
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let hir = regex_syntax::parse(source).map_err(|err| match err {
+            regex_syntax::Error::Parse(e) => e.kind().to_string(),
+            regex_syntax::Error::Translate(e) => e.kind().to_string(),
+            e => e.to_string(),
+        })?;
+        check_looks(&hir, true, true)?;
+        Ok(Self { source: source.to_string(), hir })
+    }
+
+    /// generates a string matched by the pattern
+    pub fn generate(&self, rng: &mut StdRng) -> String {
+        let mut out = String::new();
+        generate_into(&self.hir, rng, &mut out);
+        out
+    }
+}
+
+fn generate_into(hir: &Hir, rng: &mut StdRng, out: &mut String) {
+    match hir.kind() {
+        // anchors don't produce any text, `check_looks` made sure they're where they always hold
+        HirKind::Empty | HirKind::Look(_) => {}
+        HirKind::Literal(literal) => out.push_str(&String::from_utf8_lossy(&literal.0)),
+        HirKind::Class(Class::Unicode(class)) => {
+            if let Some(c) = pick_char(class, rng) {
+                out.push(c);
+            }
+        }
+        // parsing turns down patterns that match bytes past ascii, which would be sent as two
+        // byte characters
+        HirKind::Class(Class::Bytes(class)) => {
+            let ranges = class.ranges();
+            if !ranges.is_empty() {
+                let range = ranges[rng.random_range(0..ranges.len())];
+                out.push(rng.random_range(range.start()..=range.end()) as char);
+            }
+        }
+        HirKind::Repetition(repetition) => {
+            let max = repetition.max.unwrap_or(repetition.min + UNBOUNDED_EXTRA);
+            for _ in 0..rng.random_range(repetition.min..=max) {
+                generate_into(&repetition.sub, rng, out);
+            }
+        }
+        HirKind::Capture(capture) => generate_into(&capture.sub, rng, out),
+        HirKind::Concat(hirs) => {
+            for hir in hirs {
+                generate_into(hir, rng, out);
+            }
+        }
+        HirKind::Alternation(hirs) => generate_into(&hirs[rng.random_range(0..hirs.len())], rng, out),
+    }
+}
+
+/// fails unless every anchor in `hir` is at the start or end of the pattern, where it holds for
+/// any generated string. `at_start` and `at_end` say whether nothing can come before or after
+/// `hir`. word boundaries depend on the text around them and aren't supported at all
+fn check_looks(hir: &Hir, at_start: bool, at_end: bool) -> Result<(), String> {
+    match hir.kind() {
+        HirKind::Look(Look::Start | Look::StartLF | Look::StartCRLF) if !at_start => {
+            Err("`^` that isn't at the start of the pattern".to_string())
+        }
+        HirKind::Look(Look::End | Look::EndLF | Look::EndCRLF) if !at_end => {
+            Err("`$` that isn't at the end of the pattern".to_string())
+        }
+        HirKind::Look(Look::Start | Look::StartLF | Look::StartCRLF | Look::End | Look::EndLF | Look::EndCRLF) => Ok(()),
+        HirKind::Look(_) => Err("word boundaries such as `\\b` can't be generated".to_string()),
+        // a second repetition comes after the first
+        HirKind::Repetition(repetition) => {
+            let once = repetition.max.is_some_and(|max| max <= 1);
+            check_looks(&repetition.sub, at_start && once, at_end && once)
+        }
+        HirKind::Capture(capture) => check_looks(&capture.sub, at_start, at_end),
+        HirKind::Concat(hirs) => hirs.iter().enumerate().try_for_each(|(i, hir)| {
+            let empty = |hir: &Hir| hir.properties().maximum_len() == Some(0);
+            check_looks(hir, at_start && hirs[..i].iter().all(empty), at_end && hirs[i + 1..].iter().all(empty))
+        }),
+        HirKind::Alternation(hirs) => hirs.iter().try_for_each(|hir| check_looks(hir, at_start, at_end)),
+        HirKind::Empty | HirKind::Literal(_) | HirKind::Class(_) => Ok(()),
+    }
+}
+
+/// picks a character out of `class`. classes like `.`, `\d` and `\w` cover all of unicode, so when
+/// the class has any printable ascii in it the choice is narrowed to that, which is what a form
+/// field validated by the same pattern almost always expects
+fn pick_char(class: &ClassUnicode, rng: &mut StdRng) -> Option<char> {
+    let mut printable = class.clone();
+    printable.intersect(&ClassUnicode::new([ClassUnicodeRange::new(' ', '~')]));
+    let class = if printable.ranges().is_empty() { class } else { &printable };
+
+    // weight each range by its size so every character is equally likely
+    let total: u32 = class.ranges().iter().map(|r| r.end() as u32 - r.start() as u32 + 1).sum();
+    if total == 0 {
+        return None;
+    }
+    let mut n = rng.random_range(0..total);
+    for range in class.ranges() {
+        let size = range.end() as u32 - range.start() as u32 + 1;
+        if n < size {
+            // a range can span the surrogate gap, which has no chars in it
+            return Some(char::from_u32(range.start() as u32 + n).unwrap_or(range.start()));
+        }
+        n -= size;
+    }
+    None
+}
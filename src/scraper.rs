@@ -1,6 +1,5 @@
 use std::{path::PathBuf, time::Duration, net::ToSocketAddrs};
 
-use reqwest::{Client, redirect::Policy};
 use scraper::Selector;
 use tokio::{sync::mpsc::Receiver, time::timeout, net::TcpStream};
 
@@ -22,7 +21,6 @@ impl<'a> Scraper<'a> {
 
         let html = scraper::Html::parse_document(&body);
         let outer_table = html.select(&Selector::parse("td.outer")?).nth(1).ok_or("outer table not found")?;
-        let table_body = outer_table.select(&Selector::parse("table")?).next().ok_or("table not found")?;
         let table_body = outer_table.select(&Selector::parse("tbody")?).next().ok_or("table not found")?;
         let mut csv_file = csv::WriterBuilder::default().from_path(path)?;
     
@@ -34,7 +32,7 @@ impl<'a> Scraper<'a> {
             }).filter_map(|item| {
                 item.ok()
             }).collect();
-            if headers.len() > 0 {
+            if !headers.is_empty() {
                 csv_file.write_record(headers)?;
             }
             
@@ -50,14 +48,14 @@ impl<'a> Scraper<'a> {
                 text
             }).collect();
             
-            if entries.len() > 0 {
+            if !entries.is_empty() {
                 csv_file.write_record(entries)?;
             }
         }
         self.records.sort();
         self.records.dedup();
         csv_file.flush()?;
-        return Ok(());
+        Ok(())
     }
     
     pub async fn get_up_domains(&self) -> Receiver<String> {
@@ -69,7 +67,6 @@ impl<'a> Scraper<'a> {
             tokio::spawn(async move {
                 if Self::check_up(&url).await {
                     let _ = tx.send(url).await;
-                } else {
                 }
             });
         }
@@ -89,7 +86,7 @@ impl<'a> Scraper<'a> {
         // First try to resolve the domain
         let addr_iter = match clean_domain.to_socket_addrs() {
             Ok(iter) => iter,
-            Err(e) => {
+            Err(_) => {
                 return false
             },
         };
@@ -105,15 +102,10 @@ impl<'a> Scraper<'a> {
         };
         
         // Try to establish a TCP connection with a timeout
-        match timeout(
+        matches!(timeout(
             Duration::from_secs(10),
             TcpStream::connect(addr),
-        ).await {
-            Ok(Ok(_)) => true,
-            _ => {
-                false
-            },
-        }
+        ).await, Ok(Ok(_)))
     }
    
 }
@@ -1,44 +1,105 @@
-use std::{collections::HashMap, sync::atomic::{AtomicU32, Ordering}, time::Duration};
+use std::{collections::BTreeMap, sync::{Mutex, atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering}}, time::Duration};
 
-use reqwest::{Client, ClientBuilder, redirect::Policy};
+use reqwest::{Client, ClientBuilder, header::{CONTENT_TYPE, HeaderValue}, redirect::Policy};
 use tokio::{sync::{Semaphore, watch}, time::Instant};
 
+use crate::{Form, Violation};
+
+/// header naming the invalid input a request contains, so it can be found in the server's logs
+pub const VIOLATION_HEADER: &str = "X-Form-Violation";
+
+/// how the server answered requests with one kind of invalid input
+#[derive(Debug, Default, Clone, Copy)]
+struct Verdicts {
+    /// 2xx responses, the invalid input got through validation
+    accepted: u32,
+    rejected: u32,
+}
+
+/// form_builder is called with the number of the request being built, starting at 0. it gives
+/// nothing once the forms have run out, which ends the run
 pub struct Sender<T> 
-where T: FnMut() -> HashMap<String, String> + Send + Sync + 'static {
+where T: FnMut(u64) -> Option<Form> + Send + Sync + 'static {
     permits: Semaphore,
     sent: AtomicU32,
     failed: AtomicU32,
+    violations: Mutex<BTreeMap<Violation, Verdicts>>,
+    /// set when form_builder gave nothing
+    ran_out: AtomicBool,
+    next_index: AtomicU64,
+    max_open_requests: u32,
+    /// number of requests after which the forms run out, e.g. because a `unique` csv was used up
+    limit: Option<u64>,
     form_builder: T,
     client: &'static mut Client,
-    domain: String
+    domain: String,
+    seed: u64
 }
 
-impl<T> Sender<T> where T: Fn() -> HashMap<String, String> + Send + Sync + 'static {
-    pub fn new(timeout: Duration, domain: &str, form_builder: T, max_open_requests: usize) -> Result<Self, Box<dyn std::error::Error>> {
+impl<T> Sender<T> where T: Fn(u64) -> Option<Form> + Send + Sync + 'static {
+    /// `seed` is only used to report which seed the forms were generated from. the run stops after
+    /// `limit` requests if there is one
+    pub fn new(timeout: Duration, domain: &str, form_builder: T, max_open_requests: usize, seed: u64, limit: Option<u64>) -> Result<Self, Box<dyn std::error::Error>> {
         let client = Box::new(ClientBuilder::new().user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/112.0.5615.50 Safari/537.36"
         ).redirect(Policy::limited(5)).timeout(timeout).connect_timeout(timeout).build()?);
         Ok(Self {
             permits: Semaphore::new(max_open_requests),
             sent: AtomicU32::new(0),
             failed: AtomicU32::new(0),
+            violations: Mutex::default(),
+            ran_out: AtomicBool::new(false),
+            next_index: AtomicU64::new(0),
+            max_open_requests: max_open_requests as u32,
+            limit,
             form_builder,
             client: Box::leak(client),
-            domain: domain.to_owned()
+            domain: domain.to_owned(),
+            seed
         })
     }
     
-    async fn send_request(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let params: HashMap<String, String> = (self.form_builder)();
-        let mut form = reqwest::multipart::Form::new();
-        for (key, val) in params.into_iter() {
-            form = form.text(key, val);
+    fn print_summary(&self, start: Instant) {
+        let elapsed = start.elapsed().as_secs_f64();
+        println!("[*] {} requests set. {} failed requests. {} average requests per second. seed {}", 
+            self.sent.load(Ordering::Relaxed), 
+            self.failed.load(Ordering::Relaxed),
+            (self.sent.load(Ordering::Relaxed) as f64)/elapsed,
+            self.seed);
+        let violations = self.violations.lock().unwrap();
+        for (violation, verdicts) in violations.iter() {
+            let marker = if verdicts.accepted > 0 { "!" } else { "*" };
+            println!("[{marker}] {violation}: {} accepted, {} rejected", verdicts.accepted, verdicts.rejected);
+        }
+    }
+    
+    async fn send_request(&self, index: u64) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(form) = (self.form_builder)(index) else {
+            self.ran_out.store(true, Ordering::SeqCst);
+            return Ok(());
+        };
+        // the body is encoded by hand so it's byte for byte what `preview` prints
+        let mut request = self.client.post(&self.domain)
+            .header(CONTENT_TYPE, form.content_type())
+            .body(form.body());
+        if let Some(value) = form.violation.as_ref().and_then(|v| HeaderValue::from_str(&v.to_string()).ok()) {
+            request = request.header(VIOLATION_HEADER, value);
         }
-        let resp = self.client.post(&self.domain).multipart(form).send().await?;
-        if resp.status().is_success() {
+        let resp = request.send().await?;
+        let success = resp.status().is_success();
+        if success {
             self.sent.fetch_add(1, Ordering::SeqCst);
         } else {
             self.failed.fetch_add(1, Ordering::SeqCst);
         }
+        if let Some(violation) = form.violation {
+            let mut violations = self.violations.lock().unwrap();
+            let verdicts = violations.entry(violation).or_default();
+            if success {
+                verdicts.accepted += 1;
+            } else {
+                verdicts.rejected += 1;
+            }
+        }
         Ok(())
     }
     
@@ -48,29 +109,31 @@ impl<T> Sender<T> where T: Fn() -> HashMap<String, String> + Send + Sync + 'stat
         ctrlc::set_handler(move || {
             ctrlc_tx.send(true).expect("failed to send ctrlc");
         })?;
-        let self_ref = Box::leak(Box::new(self));
+        let self_ref: &'static Self = Box::leak(Box::new(self));
         let start = Instant::now();
         let mut timer = Instant::now();
         'main: loop {
             if *ctrlc_rx.borrow() {
-                let elapsed = start.elapsed().as_secs_f64();
-                println!("[*] {} requests set. {} failed requests. {} average requests per second.", 
-                    self_ref.sent.load(Ordering::Relaxed), 
-                    self_ref.failed.load(Ordering::Relaxed),
-                    (self_ref.sent.load(Ordering::Relaxed) as f64)/elapsed);
+                self_ref.print_summary(start);
                 break 'main;
             }
             let permit = self_ref.permits.acquire().await;
             if timer.elapsed().as_secs() > 10 {
-                let elapsed = start.elapsed().as_secs_f64();
-                println!("[*] {} requests set. {} failed requests. {} average requests per second.", 
-                    self_ref.sent.load(Ordering::Relaxed), 
-                    self_ref.failed.load(Ordering::Relaxed),
-                    (self_ref.sent.load(Ordering::Relaxed) as f64)/elapsed);
+                self_ref.print_summary(start);
                 timer = Instant::now();
             }
-            tokio::spawn(async {
-                self_ref.send_request().await.unwrap();
+            let index = self_ref.next_index.fetch_add(1, Ordering::Relaxed);
+            if self_ref.limit.is_some_and(|limit| index >= limit) || self_ref.ran_out.load(Ordering::SeqCst) {
+                // wait for the requests still in flight before the final summary
+                drop(permit);
+                let _all = self_ref.permits.acquire_many(self_ref.max_open_requests).await;
+                self_ref.print_summary(start);
+                let sent = self_ref.sent.load(Ordering::Relaxed) + self_ref.failed.load(Ordering::Relaxed);
+                println!("[*] forms ran out after {sent} requests");
+                break 'main;
+            }
+            tokio::spawn(async move {
+                self_ref.send_request(index).await.unwrap();
                 drop(permit);
             });
         }
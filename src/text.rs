@@ -0,0 +1,151 @@
+use std::{collections::HashMap, fs, io, path::{Path, PathBuf}, sync::Arc};
+
+use rand::{Rng, rngs::StdRng};
+
+use crate::{ConfigError, Distribution};
+
+/// how the words of a `text(...)` are picked
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextStyle {
+    /// lorem ipsum
+    Lorem,
+    /// a markov chain trained on a corpus file
+    Markov(Corpus),
+}
+
+/// word chain of a corpus file used by `text(..., markov("path"))`. each pair of words maps to
+/// the words that followed it in the corpus
+#[derive(Debug, Clone, Default)]
+pub struct Corpus {
+    path: PathBuf,
+    chain: Arc<Chain>,
+}
+
+#[derive(Debug, Default)]
+struct Chain {
+    next: HashMap<(String, String), Vec<String>>,
+    /// pairs a sentence starts with
+    starts: Vec<(String, String)>,
+}
+
+impl PartialEq for Corpus {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+}
+
+impl Corpus {
+    /// corpus for `path`, without a chain until it's loaded
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, chain: Arc::default() }
+    }
+
+    /// reads the corpus from `base` and builds the chain, which takes at least 3 words
+    pub(crate) fn load(&mut self, base: &Path) -> Result<(), ConfigError> {
+        self.path = base.join(&self.path);
+        let io_error = |source| ConfigError::Io { path: self.path.clone(), source };
+        let contents = fs::read_to_string(&self.path).map_err(io_error)?;
+        let words: Vec<&str> = contents.split_whitespace().collect();
+        if words.len() < 3 {
+            return Err(io_error(io::Error::new(io::ErrorKind::InvalidData, "corpus needs at least 3 words")));
+        }
+
+        let mut chain = Chain::default();
+        for (i, window) in words.windows(3).enumerate() {
+            let pair = (window[0].to_string(), window[1].to_string());
+            if i == 0 || ends_sentence(words[i - 1]) {
+                chain.starts.push(pair.clone());
+            }
+            chain.next.entry(pair).or_default().push(window[2].to_string());
+        }
+        self.chain = Arc::new(chain);
+        Ok(())
+    }
+
+    /// `words` words walked from the chain, starting over at the start of a sentence whenever
+    /// the walk reaches a pair the corpus ends with
+    fn generate(&self, rng: &mut StdRng, words: usize) -> Vec<String> {
+        let chain = &self.chain;
+        let mut text = Vec::with_capacity(words);
+        if chain.starts.is_empty() {
+            return text;
+        }
+        let mut pair = chain.starts[rng.random_range(0..chain.starts.len())].clone();
+        text.push(pair.0.clone());
+        while text.len() < words {
+            text.push(pair.1.clone());
+            pair = match chain.next.get(&pair) {
+                Some(next) => (pair.1, next[rng.random_range(0..next.len())].clone()),
+                None => {
+                    let start = chain.starts[rng.random_range(0..chain.starts.len())].clone();
+                    if text.len() < words {
+                        text.push(start.0.clone());
+                    }
+                    start
+                }
+            };
+        }
+        text.truncate(words);
+        text
+    }
+}
+
+fn ends_sentence(word: &str) -> bool {
+    word.ends_with(['.', '!', '?'])
+}
+
+const LOREM: &[&str] = &[
+    "lorem", "ipsum", "dolor", "sit", "amet", "consectetur", "adipiscing", "elit", "sed", "do",
+    "eiusmod", "tempor", "incididunt", "ut", "labore", "et", "dolore", "magna", "aliqua", "enim",
+    "ad", "minim", "veniam", "quis", "nostrud", "exercitation", "ullamco", "laboris", "nisi",
+    "aliquip", "ex", "ea", "commodo", "consequat", "duis", "aute", "irure", "in", "reprehenderit",
+    "voluptate", "velit", "esse", "cillum", "eu", "fugiat", "nulla", "pariatur", "excepteur", "sint",
+    "occaecat", "cupidatat", "non", "proident", "sunt", "culpa", "qui", "officia", "deserunt",
+    "mollit", "anim", "id", "est", "laborum",
+];
+
+impl TextStyle {
+    /// prose of `min..=max` words, with the number of words drawn from `length`
+    pub(crate) fn generate(&self, rng: &mut StdRng, min: usize, max: usize, length: &Distribution) -> String {
+        let words = length.sample_len(rng, min, max);
+        match self {
+            TextStyle::Lorem => lorem(rng, words),
+            TextStyle::Markov(corpus) => {
+                let mut text = corpus.generate(rng, words).join(" ");
+                if !text.is_empty() && !ends_sentence(&text) {
+                    text.push('.');
+                }
+                text
+            }
+        }
+    }
+}
+
+/// sentences of 4 to 14 words, starting with a capital letter, with the odd comma
+fn lorem(rng: &mut StdRng, words: usize) -> String {
+    let mut text = String::new();
+    let mut left_in_sentence = 0;
+    for i in 0..words {
+        let word = LOREM[rng.random_range(0..LOREM.len())];
+        if left_in_sentence == 0 {
+            if i > 0 {
+                text.push_str(". ");
+            }
+            left_in_sentence = rng.random_range(4..=14);
+            let mut chars = word.chars();
+            text.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+            text.push_str(chars.as_str());
+        } else {
+            if rng.random_bool(0.1) && left_in_sentence > 1 {
+                text.push(',');
+            }
+            text.push(' ');
+            text.push_str(word);
+        }
+        left_in_sentence -= 1;
+    }
+    if words > 0 {
+        text.push('.');
+    }
+    text
+}
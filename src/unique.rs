@@ -0,0 +1,85 @@
+use std::{collections::HashSet, sync::{Arc, Mutex}};
+
+use rand::rngs::StdRng;
+
+use crate::{FieldValue, FormFields, Parameter, Scope, TemplatePart};
+
+/// how many times in a row a `unique(...)` generator may draw a value it already gave before its
+/// value space counts as exhausted
+const MAX_ATTEMPTS: usize = 1000;
+
+/// values a `unique(...)` generator has given so far in the run. the set sits behind an `Arc`,
+/// so the tasks of a run each hold a clone but see the same values
+#[derive(Debug, Clone, Default)]
+pub struct UniqueValues {
+    state: Arc<Mutex<UniqueState>>,
+}
+
+#[derive(Debug, Default)]
+struct UniqueState {
+    /// text values of each draw, in order
+    seen: HashSet<Vec<String>>,
+    exhausted: bool,
+}
+
+/// always equal, since equality compares configs and what was handed out isn't part of one
+impl PartialEq for UniqueValues {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl UniqueValues {
+    pub fn is_exhausted(&self) -> bool {
+        self.state.lock().unwrap().exhausted
+    }
+
+    /// runs `param` until it produces values it hasn't produced before in the run. gives nothing
+    /// and marks the form in `scope` once it keeps repeating itself
+    pub(crate) fn draw(&self, param: &Parameter, rng: &mut StdRng, scope: &Scope) -> FormFields {
+        if self.is_exhausted() {
+            scope.exhausted.set(true);
+            return FormFields::new();
+        }
+        for _ in 0..MAX_ATTEMPTS {
+            let fields = param.gen_params(rng, scope);
+            if fields.is_empty() {
+                return fields;
+            }
+            let values = fields.iter().filter_map(|(_, value)| match value {
+                FieldValue::Text(text) => Some(text.clone()),
+                // parsing keeps file uploads out of `unique(...)`
+                FieldValue::File { .. } => None,
+            }).collect();
+            // the lock isn't held while generating, the same value can't get in twice anyway
+            if self.state.lock().unwrap().seen.insert(values) {
+                return fields;
+            }
+        }
+        self.state.lock().unwrap().exhausted = true;
+        scope.exhausted.set(true);
+        FormFields::new()
+    }
+}
+
+impl Parameter {
+    /// field names of the first `unique(...)` generator in here that ran out of values
+    pub(crate) fn exhausted(&self) -> Option<String> {
+        match self {
+            Parameter::Unique { param, seen } => match seen.is_exhausted() {
+                true => Some(param.field_names().join(", ")),
+                false => param.exhausted(),
+            },
+            Parameter::Let { param, .. }
+            | Parameter::Repeat { param, .. }
+            | Parameter::Maybe { param, .. } => param.exhausted(),
+            Parameter::Choose { options, .. } => options.iter().find_map(Parameter::exhausted),
+            Parameter::Weighted { options } => options.iter().find_map(|(_, option)| option.exhausted()),
+            Parameter::Template { parts, .. } => parts.iter().find_map(|part| match part {
+                TemplatePart::Generator(param) => param.exhausted(),
+                TemplatePart::Value(_) => None,
+            }),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::{fs, io, path::{Path, PathBuf}, sync::Arc};
+
+use rand::{Rng, rngs::StdRng};
+
+use crate::{ConfigError, parser::FileSource};
+
+/// guesses a content type from the file extension, falling back to application/octet-stream
+pub fn guess_content_type(filename: &str) -> &'static str {
+    let extension = Path::new(filename)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    match extension.as_str() {
+        "txt" => "text/plain",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "mp4" => "video/mp4",
+        "mp3" => "audio/mpeg",
+        _ => "application/octet-stream",
+    }
+}
+
+/// regular files directly inside `dir`, sorted so the choice made from a seed doesn't depend on
+/// the order the os lists them in
+fn list_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            files.push(entry.path());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// file name and contents of a `path(...)` upload, or of every file directly in a `dir(...)`
+#[derive(Debug, Clone, Default)]
+pub struct Uploads {
+    path: PathBuf,
+    files: Arc<Vec<(String, Vec<u8>)>>,
+}
+
+impl PartialEq for Uploads {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+}
+
+impl Uploads {
+    /// uploads of the file or directory at `path`, without contents until they're loaded
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, files: Arc::default() }
+    }
+
+    /// reads the file, or every file in the directory if `is_dir`, from `base`
+    fn load(&mut self, base: &Path, is_dir: bool) -> Result<(), ConfigError> {
+        self.path = base.join(&self.path);
+        let io_error = |source| ConfigError::Io { path: self.path.clone(), source };
+        let paths = match is_dir {
+            true => list_files(&self.path).map_err(io_error)?,
+            false => vec![self.path.clone()],
+        };
+        if paths.is_empty() {
+            return Err(io_error(io::Error::new(io::ErrorKind::NotFound, "directory contains no files")));
+        }
+        let files = paths.into_iter().map(|path| {
+            let data = fs::read(&path).map_err(|source| ConfigError::Io { path: path.clone(), source })?;
+            let filename = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+            Ok((filename, data))
+        }).collect::<Result<_, ConfigError>>()?;
+        self.files = Arc::new(files);
+        Ok(())
+    }
+}
+
+impl FileSource {
+    /// reads the files of the source from `base`
+    pub(crate) fn resolve(&mut self, base: &Path) -> Result<(), ConfigError> {
+        match self {
+            FileSource::Path(uploads) => uploads.load(base, false),
+            FileSource::Dir(uploads) => uploads.load(base, true),
+            FileSource::Random { distribution, .. } => distribution.load(base),
+        }
+    }
+
+    /// produces the file name and contents of one upload
+    pub(crate) fn pick(&self, rng: &mut StdRng) -> (String, Vec<u8>) {
+        match self {
+            FileSource::Path(uploads) | FileSource::Dir(uploads) => {
+                let files = &uploads.files;
+                if files.is_empty() {
+                    return (String::new(), Vec::new());
+                }
+                files[rng.random_range(0..files.len())].clone()
+            }
+            FileSource::Random { min_size, max_size, distribution } => {
+                let mut data = vec![0u8; distribution.sample_len(rng, *min_size, *max_size)];
+                rng.fill(&mut data[..]);
+                ("random.bin".to_string(), data)
+            }
+        }
+    }
+}